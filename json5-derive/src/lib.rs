@@ -0,0 +1,428 @@
+//! The derive macro for [`json5::Documented`][], re-exported by `json5` when its `derive` feature is
+//! enabled. Use it from there rather than depending on this crate directly.
+//!
+//! [`json5::Documented`]: https://docs.rs/json5/latest/json5/trait.Documented.html
+
+#![warn(clippy::pedantic)]
+
+use proc_macro::TokenStream;
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use quote::{ToTokens, quote};
+use syn::{
+    Attribute, Data, DeriveInput, Error, Expr, ExprLit, Fields, Generics, Ident, Lit, LitStr, Meta,
+    Path, Result, Token, Type, WherePredicate, meta::ParseNestedMeta, parse_macro_input,
+    parse_quote, punctuated::Punctuated, token,
+};
+
+/// Implement `json5::Documented` for a struct with named fields, following its `///` comments and
+/// serde attributes.
+#[proc_macro_derive(Documented, attributes(serde))]
+pub fn derive_documented(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(unsupported(input));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(unsupported(input));
+    };
+
+    let container = Container::from_attrs(&input.attrs)?;
+    let fields = fields
+        .named
+        .iter()
+        .map(|field| {
+            let ident = field.ident.clone().expect("named field");
+            Field::from_attrs(ident, field.ty.clone(), &field.attrs, container.rename_all)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    add_bounds(&mut generics, &container, &fields);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let serialize = serialize_documented(
+        &container.rename.clone().unwrap_or_else(|| name.to_string()),
+        &fields,
+    );
+    let default = documented_default(&container, &fields);
+
+    Ok(quote! {
+        impl #impl_generics ::json5::Documented for #name #ty_generics #where_clause {
+            #serialize
+            #default
+        }
+    })
+}
+
+fn unsupported(input: &DeriveInput) -> Error {
+    Error::new_spanned(
+        &input.ident,
+        "#[derive(Documented)] only supports structs with named fields",
+    )
+}
+
+// The bounds serde_derive would put on `Serialize`: the container's `bound` attribute if it has
+// one, otherwise `T: Serialize` for each type parameter used by a serialized field. Defaults that
+// come from `Default` need bounds too, for `documented_default`.
+fn add_bounds(generics: &mut Generics, container: &Container, fields: &[Field]) {
+    let params: Vec<Ident> = generics.type_params().map(|p| p.ident.clone()).collect();
+    let predicates = &mut generics.make_where_clause().predicates;
+    if let Some(bound) = &container.bound {
+        predicates.extend(bound.iter().cloned());
+    } else {
+        for param in params.iter().filter(|param| {
+            fields
+                .iter()
+                .any(|field| !field.skip && mentions(field.ty.to_token_stream(), param))
+        }) {
+            predicates.push(parse_quote!(#param: ::json5::__private::serde::Serialize));
+        }
+    }
+    for field in fields {
+        if let Some(DefaultValue::Trait) = field.default {
+            let ty = &field.ty;
+            predicates.push(parse_quote!(#ty: ::core::default::Default));
+        }
+    }
+    if !matches!(container.default, Some(DefaultValue::Path(_)))
+        && fields.iter().any(|field| field.default.is_none())
+    {
+        predicates.push(parse_quote!(Self: ::core::default::Default));
+    }
+}
+
+fn mentions(tokens: TokenStream2, ident: &Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(i) => i == *ident,
+        TokenTree::Group(group) => mentions(group.stream(), ident),
+        _ => false,
+    })
+}
+
+fn serialize_documented(name: &str, fields: &[Field]) -> TokenStream2 {
+    let len = fields.iter().filter(|field| !field.skip).count();
+    let entries = fields.iter().filter(|field| !field.skip).map(|field| {
+        let Field {
+            ident, key, doc, ..
+        } = field;
+        let entry = quote! {
+            ::json5::__private::serde::ser::SerializeStruct::serialize_field(
+                &mut state,
+                #key,
+                &::json5::Commented::new((&::json5::__private::Probe(&self.#ident)).wrap(), #doc),
+            )?;
+        };
+        match &field.skip_if {
+            Some(skip_if) => quote! {
+                if #skip_if(&self.#ident) {
+                    ::json5::__private::serde::ser::SerializeStruct::skip_field(&mut state, #key)?;
+                } else {
+                    #entry
+                }
+            },
+            None => entry,
+        }
+    });
+
+    quote! {
+        fn serialize_documented<__S: ::json5::__private::serde::Serializer>(
+            &self,
+            serializer: __S,
+        ) -> ::core::result::Result<__S::Ok, __S::Error> {
+            #[allow(unused_imports)]
+            use ::json5::__private::{ViaDocumented as _, ViaSerialize as _};
+            #[allow(unused_mut)]
+            let mut state = ::json5::__private::serde::Serializer::serialize_struct(
+                serializer,
+                #name,
+                #len,
+            )?;
+            #(#entries)*
+            ::json5::__private::serde::ser::SerializeStruct::end(state)
+        }
+    }
+}
+
+fn documented_default(container: &Container, fields: &[Field]) -> TokenStream2 {
+    let base = default_expr(container.default.as_ref());
+    let assignments = fields.iter().filter_map(|field| {
+        let ident = &field.ident;
+        let value = default_expr(Some(field.default.as_ref()?));
+        Some(quote! { #ident: #value, })
+    });
+    // Everything without a default of its own comes from the container's.
+    let rest = fields
+        .iter()
+        .any(|field| field.default.is_none())
+        .then(|| quote! { ..#base });
+
+    quote! {
+        fn documented_default() -> Self {
+            Self {
+                #(#assignments)*
+                #rest
+            }
+        }
+    }
+}
+
+fn default_expr(default: Option<&DefaultValue>) -> TokenStream2 {
+    match default {
+        Some(DefaultValue::Path(path)) => quote! { #path() },
+        Some(DefaultValue::Trait) | None => quote! { ::core::default::Default::default() },
+    }
+}
+
+/// A serde `default` attribute, either bare or naming a function.
+enum DefaultValue {
+    Trait,
+    Path(Path),
+}
+
+impl DefaultValue {
+    fn parse(meta: &ParseNestedMeta) -> Result<Self> {
+        if meta.input.peek(Token![=]) {
+            Ok(Self::Path(meta.value()?.parse::<LitStr>()?.parse()?))
+        } else {
+            Ok(Self::Trait)
+        }
+    }
+}
+
+// Serde attributes that make no difference to serializing, so can be ignored. Any attribute that
+// we neither handle nor ignore is an error, since we'd risk writing something other than what
+// `Serialize` writes.
+const CONTAINER_DESERIALIZE_ONLY: &[&str] = &[
+    "deny_unknown_fields",
+    "from",
+    "try_from",
+    "expecting",
+    "crate",
+];
+const FIELD_DESERIALIZE_ONLY: &[&str] = &["alias", "deserialize_with", "borrow"];
+
+fn is_any(meta: &ParseNestedMeta, names: &[&str]) -> bool {
+    names.iter().any(|name| meta.path.is_ident(name))
+}
+
+struct Container {
+    rename: Option<String>,
+    rename_all: Option<RenameAll>,
+    default: Option<DefaultValue>,
+    bound: Option<Punctuated<WherePredicate, Token![,]>>,
+}
+
+impl Container {
+    fn from_attrs(attrs: &[Attribute]) -> Result<Self> {
+        let mut container = Self {
+            rename: None,
+            rename_all: None,
+            default: None,
+            bound: None,
+        };
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    if let Some(name) = serialize_name(&meta)? {
+                        container.rename = Some(name.value());
+                    }
+                } else if meta.path.is_ident("bound") {
+                    if let Some(bound) = serialize_name(&meta)? {
+                        container.bound = Some(bound.parse_with(Punctuated::parse_terminated)?);
+                    }
+                } else if meta.path.is_ident("rename_all") {
+                    if let Some(rule) = serialize_name(&meta)? {
+                        container.rename_all = Some(RenameAll::parse(&rule)?);
+                    }
+                } else if meta.path.is_ident("default") {
+                    container.default = Some(DefaultValue::parse(&meta)?);
+                } else if is_any(&meta, CONTAINER_DESERIALIZE_ONLY) {
+                    skip_value(&meta)?;
+                } else {
+                    return Err(not_supported(&meta));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(container)
+    }
+}
+
+struct Field {
+    ident: Ident,
+    ty: Type,
+    key: String,
+    doc: String,
+    skip: bool,
+    skip_if: Option<Path>,
+    default: Option<DefaultValue>,
+}
+
+impl Field {
+    fn from_attrs(
+        ident: Ident,
+        ty: Type,
+        attrs: &[Attribute],
+        rename_all: Option<RenameAll>,
+    ) -> Result<Self> {
+        let name = ident.to_string();
+        let name = name.strip_prefix("r#").unwrap_or(&name);
+        let mut field = Self {
+            key: rename_all.map_or_else(|| name.to_owned(), |rule| rule.apply(name)),
+            doc: doc(attrs),
+            ident,
+            ty,
+            skip: false,
+            skip_if: None,
+            default: None,
+        };
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    if let Some(key) = serialize_name(&meta)? {
+                        field.key = key.value();
+                    }
+                } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_serializing") {
+                    field.skip = true;
+                    // `skip` implies `skip_deserializing`, which implies `default`.
+                    if meta.path.is_ident("skip") {
+                        field.default.get_or_insert(DefaultValue::Trait);
+                    }
+                } else if meta.path.is_ident("skip_deserializing") {
+                    field.default.get_or_insert(DefaultValue::Trait);
+                } else if meta.path.is_ident("skip_serializing_if") {
+                    field.skip_if = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                } else if meta.path.is_ident("default") {
+                    field.default = Some(DefaultValue::parse(&meta)?);
+                } else if is_any(&meta, FIELD_DESERIALIZE_ONLY) {
+                    skip_value(&meta)?;
+                } else {
+                    return Err(not_supported(&meta));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(field)
+    }
+}
+
+/// Joins the `///` comments on an item into one comment, without the space after each `///`.
+fn doc(attrs: &[Attribute]) -> String {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) if meta.path.is_ident("doc") => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(s), ..
+                }) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .flat_map(|doc| {
+            doc.split('\n')
+                .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end().to_owned())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    lines.join("\n").trim_matches('\n').to_owned()
+}
+
+/// Parses `name = "..."` or `name(serialize = "...")`, returning the value for serializing, if
+/// any.
+fn serialize_name(meta: &ParseNestedMeta) -> Result<Option<LitStr>> {
+    if meta.input.peek(Token![=]) {
+        return Ok(Some(meta.value()?.parse()?));
+    }
+    let mut name = None;
+    meta.parse_nested_meta(|meta| {
+        if meta.path.is_ident("serialize") {
+            name = Some(meta.value()?.parse()?);
+        } else {
+            skip_value(&meta)?;
+        }
+        Ok(())
+    })?;
+    Ok(name)
+}
+
+/// Skips over the value of an attribute we don't care about, like `alias = "..."` or
+/// `rename(deserialize = "...")`.
+fn skip_value(meta: &ParseNestedMeta) -> Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<Lit>()?;
+    } else if meta.input.peek(token::Paren) {
+        meta.input.parse::<proc_macro2::Group>()?;
+    }
+    Ok(())
+}
+
+fn not_supported(meta: &ParseNestedMeta) -> Error {
+    let name = meta
+        .path
+        .get_ident()
+        .map_or_else(String::new, ToString::to_string);
+    meta.error(format!(
+        "#[serde({name})] is not supported by #[derive(Documented)]"
+    ))
+}
+
+/// The `rename_all` rules serde supports, as they apply to `snake_case` field names.
+#[derive(Clone, Copy)]
+enum RenameAll {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameAll {
+    fn parse(rule: &LitStr) -> Result<Self> {
+        Ok(match rule.value().as_str() {
+            "lowercase" => Self::Lower,
+            "UPPERCASE" => Self::Upper,
+            "PascalCase" => Self::Pascal,
+            "camelCase" => Self::Camel,
+            "snake_case" => Self::Snake,
+            "SCREAMING_SNAKE_CASE" => Self::ScreamingSnake,
+            "kebab-case" => Self::Kebab,
+            "SCREAMING-KEBAB-CASE" => Self::ScreamingKebab,
+            _ => return Err(Error::new_spanned(rule, "unknown rename rule")),
+        })
+    }
+
+    fn apply(self, field: &str) -> String {
+        match self {
+            Self::Lower | Self::Snake => field.to_owned(),
+            Self::Upper | Self::ScreamingSnake => field.to_ascii_uppercase(),
+            Self::Pascal => field
+                .split('_')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    chars.next().map_or_else(String::new, |first| {
+                        first.to_ascii_uppercase().to_string() + chars.as_str()
+                    })
+                })
+                .collect(),
+            Self::Camel => {
+                let pascal = Self::Pascal.apply(field);
+                let mut chars = pascal.chars();
+                chars.next().map_or_else(String::new, |first| {
+                    first.to_ascii_lowercase().to_string() + chars.as_str()
+                })
+            }
+            Self::Kebab => field.replace('_', "-"),
+            Self::ScreamingKebab => field.to_ascii_uppercase().replace('_', "-"),
+        }
+    }
+}
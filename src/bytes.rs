@@ -0,0 +1,124 @@
+use std::io::{self, Write};
+
+/// How to write (or read) byte arrays, e.g. [`serde_bytes::ByteBuf`][]. All but
+/// [`ByteEncoding::Array`] write a string.
+///
+/// [`serde_bytes::ByteBuf`]: https://docs.rs/serde_bytes/latest/serde_bytes/struct.ByteBuf.html
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum ByteEncoding {
+    /// Two hex digits per byte, e.g. `"4a534f4e35"`. Either case is read.
+    #[default]
+    Hex,
+    /// Base64 with the standard alphabet and `=` padding, e.g. `"SlNPTjU="`. Padding is optional
+    /// when reading.
+    Base64,
+    /// Base64 with the URL and filename safe alphabet (`-` and `_` instead of `+` and `/`) and no
+    /// padding, e.g. `"SlNPTjU"`. Padding is optional when reading.
+    Base64UrlSafe,
+    /// An array of integers from 0 to 255, e.g. `[74, 83, 79, 78, 53]`. Object keys have to be
+    /// strings, so bytes used as keys are written as hex instead.
+    Array,
+}
+
+const STANDARD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Writes the bytes as the contents of a string (without the quotes). [`ByteEncoding::Array`]
+/// isn't a string, so gets hex.
+pub fn encode(w: &mut impl Write, bytes: &[u8], encoding: ByteEncoding) -> io::Result<()> {
+    match encoding {
+        ByteEncoding::Hex | ByteEncoding::Array => {
+            for b in bytes {
+                write!(w, "{b:02x}")?;
+            }
+        }
+        ByteEncoding::Base64 => encode_base64(w, bytes, STANDARD, true)?,
+        ByteEncoding::Base64UrlSafe => encode_base64(w, bytes, URL_SAFE, false)?,
+    }
+    Ok(())
+}
+
+fn encode_base64(
+    w: &mut impl Write,
+    bytes: &[u8],
+    alphabet: &[u8; 64],
+    padding: bool,
+) -> io::Result<()> {
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        // 1, 2 or 3 bytes take 2, 3 or 4 characters.
+        for i in 0..4 {
+            if i <= chunk.len() {
+                w.write_all(&[alphabet[(n >> (18 - 6 * i)) as usize & 63]])?;
+            } else if padding {
+                w.write_all(b"=")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decodes the contents of a string. On failure returns the byte offset in `s` of the offending
+/// character, or `s.len()` if the string ends too soon. [`ByteEncoding::Array`] isn't a string,
+/// so gets hex, as with [`encode`].
+pub fn decode(s: &str, encoding: ByteEncoding) -> Result<Vec<u8>, usize> {
+    match encoding {
+        ByteEncoding::Hex | ByteEncoding::Array => decode_hex(s),
+        ByteEncoding::Base64 => decode_base64(s, STANDARD),
+        ByteEncoding::Base64UrlSafe => decode_base64(s, URL_SAFE),
+    }
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, usize> {
+    let mut bytes = Vec::with_capacity(s.len() / 2);
+    let mut high = None;
+    for (i, c) in s.char_indices() {
+        let digit = c.to_digit(16).ok_or(i)?;
+        match high.take() {
+            Some(high) => {
+                bytes.push(u8::try_from(high * 16 + digit).expect("two hex digits fit in a u8"));
+            }
+            None => high = Some(digit),
+        }
+    }
+    if high.is_some() {
+        return Err(s.len());
+    }
+    Ok(bytes)
+}
+
+fn decode_base64(s: &str, alphabet: &[u8; 64]) -> Result<Vec<u8>, usize> {
+    let data = s.trim_end_matches('=');
+    let padding = s.len() - data.len();
+    if padding > 2 || (padding > 0 && !s.len().is_multiple_of(4)) {
+        return Err(data.len());
+    }
+    if data.len() % 4 == 1 {
+        return Err(s.len());
+    }
+
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    let (mut acc, mut bits) = (0u32, 0);
+    for (i, c) in data.char_indices() {
+        let value = u8::try_from(c)
+            .ok()
+            .and_then(|c| alphabet.iter().position(|&a| a == c))
+            .ok_or(i)?;
+        acc = acc << 6 | u32::try_from(value).expect("an index into 64 characters fits in a u32");
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push(u8::try_from(acc >> bits).expect("we keep fewer than 16 bits"));
+            acc &= (1 << bits) - 1;
+        }
+    }
+    // The bits left over at the end should be zero, so that each string of bytes has exactly one
+    // encoding.
+    if acc != 0 {
+        return Err(data.len() - 1);
+    }
+    Ok(bytes)
+}
@@ -1,3 +1,5 @@
+use ucd_trie::TrieSet;
+
 use crate::unicode::{
     CONNECTOR_PUNCTUATION, DECIMAL_NUMBER, LETTER_NUMBER, LOWERCASE_LETTER, MODIFIER_LETTER,
     NONSPACING_MARK, OTHER_LETTER, SPACE_SEPARATOR, SPACING_MARK, TITLECASE_LETTER,
@@ -19,25 +21,108 @@ pub fn is_json5_line_terminator(c: char) -> bool {
     matches!(c, '\u{000A}' | '\u{000D}' | '\u{2028}' | '\u{2029}')
 }
 
+/// Splits the input into lines on JSON5 line terminators, treating <CR><LF> as a single
+/// terminator, in the same way as [`Position::from_offset`](crate::Position::from_offset).
+pub fn lines(input: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(input);
+    std::iter::from_fn(move || {
+        let s = rest?;
+        let Some((i, c)) = s.char_indices().find(|&(_, c)| is_json5_line_terminator(c)) else {
+            rest = None;
+            return Some(s);
+        };
+        let end = if s[i..].starts_with("\r\n") {
+            i + 2
+        } else {
+            i + c.len_utf8()
+        };
+        rest = Some(&s[end..]);
+        Some(&s[..i])
+    })
+}
+
 /// <https://262.ecma-international.org/5.1/#sec-7.6>
-pub fn is_json5_identifier_start(c: char) -> bool {
+pub const fn is_json5_identifier_start(c: char) -> bool {
     matches!(c, '$' | '_')
-        || UPPERCASE_LETTER.contains_char(c)
-        || LOWERCASE_LETTER.contains_char(c)
-        || TITLECASE_LETTER.contains_char(c)
-        || MODIFIER_LETTER.contains_char(c)
-        || OTHER_LETTER.contains_char(c)
-        || LETTER_NUMBER.contains_char(c)
+        || contains(UPPERCASE_LETTER, c)
+        || contains(LOWERCASE_LETTER, c)
+        || contains(TITLECASE_LETTER, c)
+        || contains(MODIFIER_LETTER, c)
+        || contains(OTHER_LETTER, c)
+        || contains(LETTER_NUMBER, c)
 }
 
 /// <https://262.ecma-international.org/5.1/#sec-7.6>
-pub fn is_json5_identifier(c: char) -> bool {
+pub const fn is_json5_identifier(c: char) -> bool {
     is_json5_identifier_start(c)
         || matches!(c, '\u{200C}' | '\u{200D}')
-        || NONSPACING_MARK.contains_char(c)
-        || SPACING_MARK.contains_char(c)
-        || DECIMAL_NUMBER.contains_char(c)
-        || CONNECTOR_PUNCTUATION.contains_char(c)
+        || contains(NONSPACING_MARK, c)
+        || contains(SPACING_MARK, c)
+        || contains(DECIMAL_NUMBER, c)
+        || contains(CONNECTOR_PUNCTUATION, c)
+}
+
+/// Whether the string is a valid identifier, and so can be used as an object key without quotes.
+/// This is a `const fn` so that the [`json5!`](crate::json5!) macro can check keys at compile
+/// time, hence the hand written loop.
+pub const fn is_json5_identifier_name(s: &str) -> bool {
+    let mut rest = s;
+    while let Some(c) = first_char(rest) {
+        let valid = if rest.len() == s.len() {
+            is_json5_identifier_start(c)
+        } else {
+            is_json5_identifier(c)
+        };
+        if !valid {
+            return false;
+        }
+        rest = rest.split_at(c.len_utf8()).1;
+    }
+    !s.is_empty()
+}
+
+// `s.chars().next()`, which isn't available in a `const fn`.
+const fn first_char(s: &str) -> Option<char> {
+    let bytes = s.as_bytes();
+    let Some(&first) = bytes.first() else {
+        return None;
+    };
+    // The number of leading ones in the first byte is the length of the sequence (except for
+    // ASCII), and the continuation bytes carry 6 bits each.
+    let len = match first.leading_ones() {
+        0 => return Some(first as char),
+        n => n as usize,
+    };
+    let mut c = (first as u32) & (0x7F >> len);
+    let mut i = 1;
+    while i < len {
+        c = c << 6 | (bytes[i] as u32 & 0x3F);
+        i += 1;
+    }
+    char::from_u32(c)
+}
+
+// `set.contains_char(c)`, which isn't available in a `const fn`. The lookup is the same as
+// ucd-trie's.
+const fn contains(set: &TrieSet, c: char) -> bool {
+    let cp = c as usize;
+    let chunk = if cp < 0x800 {
+        set.tree1_level1[cp >> 6]
+    } else if cp < 0x10000 {
+        let i = (cp >> 6) - 0x20;
+        if i >= set.tree2_level1.len() {
+            return false;
+        }
+        set.tree2_level2[set.tree2_level1[i] as usize]
+    } else {
+        let i = (cp >> 12) - 0x10;
+        if i >= set.tree3_level1.len() {
+            return false;
+        }
+        let leaf = set.tree3_level2[set.tree3_level1[i] as usize * 64 + ((cp >> 6) & 0x3F)];
+        set.tree3_level3[leaf as usize]
+    };
+    (chunk >> (cp & 0x3F)) & 1 == 1
 }
 
 /// <https://spec.json5.org/#strings>
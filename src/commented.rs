@@ -0,0 +1,117 @@
+use std::{
+    cell::RefCell,
+    ops::{Deref, DerefMut},
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The name of the newtype struct that [`Commented`] serializes as, which tells our
+/// [`Serializer`](crate::Serializer) to pick up the comment.
+pub(crate) const NAME: &str = "$json5::private::Commented";
+
+thread_local! {
+    // The comment for the `Commented` currently being serialized. It's passed out of band so that
+    // other serializers see just the value.
+    static COMMENT: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Takes the comment for the [`Commented`] currently being serialized.
+pub(crate) fn take_comment() -> Option<String> {
+    COMMENT.take()
+}
+
+/// A value with a comment, which is written as `//` line comments before the value when
+/// serializing as JSON5 (before the key, for an entry of an object).
+///
+/// Comments are left out of compact output and out of plain JSON. Other serializers see just the
+/// value. Deserializing reads just the value, leaving the comment empty.
+///
+/// # Example
+/// ```
+/// use json5::Commented;
+/// use serde_derive::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     port: Commented<u16>,
+///     hosts: Vec<Commented<&'static str>>,
+/// }
+///
+/// let config = Config {
+///     port: Commented::new(8080, "Port the HTTP server listens on"),
+///     hosts: vec![Commented::new("localhost", "For testing.\nRemove me!")],
+/// };
+///
+/// assert_eq!(json5::to_string(&config)?, "{
+///   // Port the HTTP server listens on
+///   port: 8080,
+///   hosts: [
+///     // For testing.
+///     // Remove me!
+///     \"localhost\",
+///   ],
+/// }");
+/// # Ok::<(), json5::Error>(())
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Commented<T> {
+    pub value: T,
+    /// The comment, which may span multiple lines. Nothing is written if it's empty.
+    pub comment: String,
+}
+
+impl<T> Commented<T> {
+    pub fn new(value: T, comment: impl Into<String>) -> Self {
+        Self {
+            value,
+            comment: comment.into(),
+        }
+    }
+}
+
+impl<T> Deref for Commented<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Commented<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: Serialize> Serialize for Commented<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let _guard = CommentGuard::set(self.comment.clone());
+        serializer.serialize_newtype_struct(NAME, &self.value)
+    }
+}
+
+// Sets the comment while a `Commented` is being serialized, and puts back whatever was there before
+// (normally nothing) when dropped, even if serializing fails or panics. Otherwise a serializer that
+// isn't ours could leave the comment behind for whatever comes next on the same thread.
+struct CommentGuard(Option<String>);
+
+impl CommentGuard {
+    fn set(comment: String) -> Self {
+        Self(COMMENT.replace(Some(comment)))
+    }
+}
+
+impl Drop for CommentGuard {
+    fn drop(&mut self) {
+        COMMENT.set(self.0.take());
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Commented<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(|value| Self {
+            value,
+            comment: String::new(),
+        })
+    }
+}
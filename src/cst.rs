@@ -0,0 +1,558 @@
+//! A lossless concrete syntax tree for JSON5.
+//!
+//! Deserializing to a type (or a [`Value`](crate::Value)) throws away everything that doesn't
+//! affect the meaning of the document: comments, whitespace, quote style, how numbers are written,
+//! and so on. A [`Document`] keeps all of it, so that a document can be inspected or modified and
+//! then printed back without disturbing anything else. Printing an unmodified document gives back
+//! exactly the input it was parsed from.
+//!
+//! Every significant token ([`Token`]) carries the whitespace and comments ([`Trivia`]) around it.
+//! Trivia on the same line after a token belongs to that token, and everything else belongs to the
+//! token that follows it, so a comment on the line above an object member belongs to that member.
+//!
+//! ```
+//! use json5::cst::{Document, Value};
+//!
+//! let input = "{
+//!   // The port to listen on.
+//!   port: 0x1f90, // 8080
+//! }
+//! ";
+//! let document: Document = input.parse()?;
+//! assert_eq!(document.to_string(), input);
+//!
+//! let Value::Object(object) = &document.value else { unreachable!() };
+//! let member = &object.members[0];
+//! assert_eq!(member.key.text, "port");
+//! assert_eq!(member.key.leading[1].text, "// The port to listen on.");
+//! assert_eq!(member.value.to_string(), "0x1f90");
+//! assert_eq!(member.comma.as_ref().and_then(|comma| comma.span.clone()), Some(44..45));
+//! # Ok::<(), json5::Error>(())
+//! ```
+
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::Range,
+    str::FromStr,
+};
+
+use serde::{Deserialize, de::IgnoredAny};
+
+use crate::{
+    de::Deserializer,
+    error::{Error, ErrorCode, Result},
+};
+
+/// Parse a JSON5 string into a [`Document`].
+///
+/// # Errors
+/// Fails if the JSON5 is malformed.
+pub fn parse(input: &str) -> Result<Document> {
+    Parser {
+        de: Deserializer::from_str(input),
+        input,
+    }
+    .document()
+}
+
+/// A whole JSON5 document: a single value, surrounded by trivia.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    /// The value. Any trivia before it belongs to its first token.
+    pub value: Value,
+    /// Trivia after the value that doesn't belong to its last token.
+    pub trailing: Vec<Trivia>,
+}
+
+/// A JSON5 value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// `null`, a bool, a number, or a string.
+    Scalar(Token),
+    /// An array.
+    Array(Array),
+    /// An object.
+    Object(Object),
+}
+
+/// An array: `[`, any number of elements, then `]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Array {
+    /// The opening `[`.
+    pub open: Token,
+    /// The elements of the array.
+    pub elements: Vec<Element>,
+    /// The closing `]`.
+    pub close: Token,
+}
+
+/// An element of an [`Array`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Element {
+    /// The value.
+    pub value: Value,
+    /// The comma after the value. Only the last element can be without one.
+    pub comma: Option<Token>,
+}
+
+/// An object: `{`, any number of members, then `}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Object {
+    /// The opening `{`.
+    pub open: Token,
+    /// The members of the object, in the order they were written.
+    pub members: Vec<Member>,
+    /// The closing `}`.
+    pub close: Token,
+}
+
+/// A member of an [`Object`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    /// The key, as a [`TokenKind::String`] or a [`TokenKind::Identifier`].
+    pub key: Token,
+    /// The `:` between the key and the value.
+    pub colon: Token,
+    /// The value.
+    pub value: Value,
+    /// The comma after the value. Only the last member can be without one.
+    pub comma: Option<Token>,
+}
+
+/// A significant token, along with the trivia around it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    /// What kind of token this is.
+    pub kind: TokenKind,
+    /// The token as written (e.g. a string includes its quotes and any escape sequences).
+    pub text: String,
+    /// The range of bytes in the input the token was parsed from, or `None` if it wasn't parsed.
+    pub span: Option<Range<usize>>,
+    /// Trivia before the token.
+    pub leading: Vec<Trivia>,
+    /// Trivia after the token, up to and including the end of the line.
+    pub trailing: Vec<Trivia>,
+}
+
+/// The kinds of [`Token`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// `[`
+    OpenBracket,
+    /// `]`
+    CloseBracket,
+    /// `{`
+    OpenBrace,
+    /// `}`
+    CloseBrace,
+    /// `:`
+    Colon,
+    /// `,`
+    Comma,
+    /// `null`
+    Null,
+    /// `true` or `false`
+    Bool,
+    /// Any number, including `Infinity` and `NaN`.
+    Number,
+    /// A single or double quoted string.
+    String,
+    /// An object key without quotes.
+    Identifier,
+}
+
+/// Whitespace or a comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia {
+    /// What kind of trivia this is.
+    pub kind: TriviaKind,
+    /// The trivia as written.
+    pub text: String,
+    /// The range of bytes in the input the trivia was parsed from, or `None` if it wasn't parsed.
+    pub span: Option<Range<usize>>,
+}
+
+/// The kinds of [`Trivia`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TriviaKind {
+    /// A run of whitespace, not including line terminators.
+    Whitespace,
+    /// A single line terminator (where `\r\n` counts as one).
+    Newline,
+    /// A `//` comment, not including the line terminator at the end.
+    LineComment,
+    /// A `/* */` comment.
+    BlockComment,
+}
+
+impl Value {
+    /// The first token of the value, which holds the trivia before it.
+    #[must_use]
+    pub fn first_token(&self) -> &Token {
+        match self {
+            Value::Scalar(token) => token,
+            Value::Array(array) => &array.open,
+            Value::Object(object) => &object.open,
+        }
+    }
+
+    /// The first token of the value, which holds the trivia before it.
+    pub fn first_token_mut(&mut self) -> &mut Token {
+        match self {
+            Value::Scalar(token) => token,
+            Value::Array(array) => &mut array.open,
+            Value::Object(object) => &mut object.open,
+        }
+    }
+
+    /// The last token of the value, which holds the trivia after it.
+    #[must_use]
+    pub fn last_token(&self) -> &Token {
+        match self {
+            Value::Scalar(token) => token,
+            Value::Array(array) => &array.close,
+            Value::Object(object) => &object.close,
+        }
+    }
+
+    /// The last token of the value, which holds the trivia after it.
+    pub fn last_token_mut(&mut self) -> &mut Token {
+        match self {
+            Value::Scalar(token) => token,
+            Value::Array(array) => &mut array.close,
+            Value::Object(object) => &mut object.close,
+        }
+    }
+}
+
+impl Member {
+    /// The key, with any quotes and escape sequences resolved.
+    #[must_use]
+    pub fn key(&self) -> String {
+        Deserializer::from_str(&self.key.text)
+            .parse_key()
+            .map_or_else(|_| self.key.text.clone(), |(_, key)| key.to_owned())
+    }
+}
+
+impl Token {
+    /// Construct a token with no trivia that wasn't parsed from anywhere.
+    #[must_use]
+    pub fn new(kind: TokenKind, text: impl Into<String>) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+            span: None,
+            leading: Vec::new(),
+            trailing: Vec::new(),
+        }
+    }
+}
+
+impl Trivia {
+    /// Construct trivia that wasn't parsed from anywhere.
+    #[must_use]
+    pub fn new(kind: TriviaKind, text: impl Into<String>) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+            span: None,
+        }
+    }
+}
+
+impl FromStr for Document {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        parse(s)
+    }
+}
+
+impl Display for Document {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)?;
+        self.trailing
+            .iter()
+            .try_for_each(|trivia| write!(f, "{trivia}"))
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Value::Scalar(token) => write!(f, "{token}"),
+            Value::Array(array) => write!(f, "{array}"),
+            Value::Object(object) => write!(f, "{object}"),
+        }
+    }
+}
+
+impl Display for Array {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.open)?;
+        for element in &self.elements {
+            write!(f, "{element}")?;
+        }
+        write!(f, "{}", self.close)
+    }
+}
+
+impl Display for Element {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)?;
+        if let Some(comma) = &self.comma {
+            write!(f, "{comma}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Object {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.open)?;
+        for member in &self.members {
+            write!(f, "{member}")?;
+        }
+        write!(f, "{}", self.close)
+    }
+}
+
+impl Display for Member {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}{}{}", self.key, self.colon, self.value)?;
+        if let Some(comma) = &self.comma {
+            write!(f, "{comma}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for trivia in &self.leading {
+            write!(f, "{trivia}")?;
+        }
+        write!(f, "{}", self.text)?;
+        for trivia in &self.trailing {
+            write!(f, "{trivia}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Trivia {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+// Leans on the deserializer to find the extent of (and validate) each token.
+struct Parser<'a> {
+    de: Deserializer<'a>,
+    input: &'a str,
+}
+
+impl Parser<'_> {
+    fn document(&mut self) -> Result<Document> {
+        let leading = self.trivia(false)?;
+        let value = self.value(leading)?;
+        let trailing = self.trivia(false)?;
+        if let Some((offset, _)) = self.de.peek() {
+            return Err(self.de.err_at(offset, ErrorCode::TrailingCharacters));
+        }
+        Ok(Document { value, trailing })
+    }
+
+    // Parses trivia up to the next token, or just up to the end of the line if `same_line`.
+    fn trivia(&mut self, same_line: bool) -> Result<Vec<Trivia>> {
+        let mut trivia = Vec::new();
+        while let Some((start, c)) = self.de.peek() {
+            let kind = if crate::char::is_json5_line_terminator(c) {
+                self.de.next();
+                if c == '\r' && self.de.peek().is_some_and(|(_, c)| c == '\n') {
+                    self.de.next();
+                }
+                TriviaKind::Newline
+            } else if crate::char::is_json5_whitespace(c) {
+                while self.de.peek().is_some_and(|(_, c)| {
+                    crate::char::is_json5_whitespace(c) && !crate::char::is_json5_line_terminator(c)
+                }) {
+                    self.de.next();
+                }
+                TriviaKind::Whitespace
+            } else if c == '/' {
+                self.de.next();
+                self.comment(start)?
+            } else {
+                break;
+            };
+            let end = self.de.offset();
+            trivia.push(Trivia {
+                kind,
+                text: self.input[start..end].to_owned(),
+                span: Some(start..end),
+            });
+            if same_line && kind == TriviaKind::Newline {
+                break;
+            }
+        }
+        Ok(trivia)
+    }
+
+    // https://spec.json5.org/#comments
+    // `start` is the offset of the slash we've just passed.
+    fn comment(&mut self, start: usize) -> Result<TriviaKind> {
+        match self.de.next() {
+            Some((_, '/')) => {
+                while self
+                    .de
+                    .peek()
+                    .is_some_and(|(_, c)| !crate::char::is_json5_line_terminator(c))
+                {
+                    self.de.next();
+                }
+                Ok(TriviaKind::LineComment)
+            }
+            Some((_, '*')) => {
+                while let Some((_, c)) = self.de.next() {
+                    if c == '*' && self.de.peek().is_some_and(|(_, c)| c == '/') {
+                        self.de.next();
+                        break;
+                    }
+                }
+                Ok(TriviaKind::BlockComment)
+            }
+            Some((offset, _)) => Err(self.de.err_at(offset, ErrorCode::ExpectedComment)),
+            None => Err(self
+                .de
+                .with_position(Error::new(ErrorCode::EofParsingComment), start)),
+        }
+    }
+
+    // Makes a token out of everything from `start` to where we've parsed up to.
+    fn token(&mut self, kind: TokenKind, start: usize, leading: Vec<Trivia>) -> Result<Token> {
+        let end = self.de.offset();
+        Ok(Token {
+            kind,
+            text: self.input[start..end].to_owned(),
+            span: Some(start..end),
+            leading,
+            trailing: self.trivia(true)?,
+        })
+    }
+
+    fn value(&mut self, leading: Vec<Trivia>) -> Result<Value> {
+        let Some((start, c)) = self.de.peek() else {
+            return Err(self.de.err_at(self.de.offset(), ErrorCode::EofParsingValue));
+        };
+        let kind = match c {
+            '[' => return self.array(start, leading).map(Value::Array),
+            '{' => return self.object(start, leading).map(Value::Object),
+            'n' => TokenKind::Null,
+            't' | 'f' => TokenKind::Bool,
+            '"' | '\'' => TokenKind::String,
+            _ => TokenKind::Number,
+        };
+        IgnoredAny::deserialize(&mut self.de).map_err(|err| self.de.with_position(err, start))?;
+        self.token(kind, start, leading).map(Value::Scalar)
+    }
+
+    fn array(&mut self, start: usize, leading: Vec<Trivia>) -> Result<Array> {
+        self.de.next();
+        self.de.enter_collection(start)?;
+        let open = self.token(TokenKind::OpenBracket, start, leading)?;
+        let mut elements: Vec<Element> = Vec::new();
+        let close = loop {
+            let leading = self.trivia(false)?;
+            let Some((offset, c)) = self.de.peek() else {
+                return Err(self.de.err_at(start, ErrorCode::EofParsingArray));
+            };
+            let last = elements
+                .last_mut()
+                .filter(|element| element.comma.is_none());
+            match (c, last) {
+                (']', _) => {
+                    self.de.next();
+                    break self.token(TokenKind::CloseBracket, offset, leading)?;
+                }
+                (',', Some(last)) => {
+                    self.de.next();
+                    last.comma = Some(self.token(TokenKind::Comma, offset, leading)?);
+                }
+                (_, Some(_)) => return Err(self.de.err_at(offset, ErrorCode::ExpectedComma)),
+                (_, None) => elements.push(Element {
+                    value: self.value(leading)?,
+                    comma: None,
+                }),
+            }
+        };
+        self.de.leave_collection();
+        Ok(Array {
+            open,
+            elements,
+            close,
+        })
+    }
+
+    fn object(&mut self, start: usize, leading: Vec<Trivia>) -> Result<Object> {
+        self.de.next();
+        self.de.enter_collection(start)?;
+        let open = self.token(TokenKind::OpenBrace, start, leading)?;
+        let mut members: Vec<Member> = Vec::new();
+        let close = loop {
+            let leading = self.trivia(false)?;
+            let Some((offset, c)) = self.de.peek() else {
+                return Err(self.de.err_at(start, ErrorCode::EofParsingObject));
+            };
+            let last = members.last_mut().filter(|member| member.comma.is_none());
+            match (c, last) {
+                ('}', _) => {
+                    self.de.next();
+                    break self.token(TokenKind::CloseBrace, offset, leading)?;
+                }
+                (',', Some(last)) => {
+                    self.de.next();
+                    last.comma = Some(self.token(TokenKind::Comma, offset, leading)?);
+                }
+                (_, Some(_)) => return Err(self.de.err_at(offset, ErrorCode::ExpectedComma)),
+                (_, None) => members.push(self.member(start, offset, leading)?),
+            }
+        };
+        self.de.leave_collection();
+        Ok(Object {
+            open,
+            members,
+            close,
+        })
+    }
+
+    fn member(&mut self, object: usize, start: usize, leading: Vec<Trivia>) -> Result<Member> {
+        let kind = match self.de.peek() {
+            Some((_, '"' | '\'')) => TokenKind::String,
+            _ => TokenKind::Identifier,
+        };
+        self.de
+            .parse_key()
+            .map_err(|err| self.de.with_position(err, start))?;
+        let key = self.token(kind, start, leading)?;
+
+        let leading = self.trivia(false)?;
+        let colon = match self.de.next() {
+            Some((offset, ':')) => self.token(TokenKind::Colon, offset, leading)?,
+            Some((offset, _)) => return Err(self.de.err_at(offset, ErrorCode::ExpectedColon)),
+            None => return Err(self.de.err_at(object, ErrorCode::EofParsingObject)),
+        };
+
+        let leading = self.trivia(false)?;
+        if self.de.peek().is_none() {
+            return Err(self.de.err_at(object, ErrorCode::EofParsingObject));
+        }
+        Ok(Member {
+            key,
+            colon,
+            value: self.value(leading)?,
+            comma: None,
+        })
+    }
+}
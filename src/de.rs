@@ -1,13 +1,27 @@
+mod read;
+
 use std::{
+    collections::{HashMap, hash_map::Entry},
     fmt::Display,
-    iter::Peekable,
-    ops::Deref,
-    str::{CharIndices, FromStr},
+    io,
+    iter::FusedIterator,
+    marker::PhantomData,
+    ops::{Deref, Range},
+    str::FromStr,
+};
+
+use serde::{
+    Deserialize,
+    de::{DeserializeOwned, IgnoredAny, Visitor},
+    forward_to_deserialize_any,
 };
 
-use serde::{Deserialize, de::Visitor, forward_to_deserialize_any};
+use crate::{
+    bytes::{self, ByteEncoding},
+    error::{Error, ErrorCode, Position, Result},
+};
 
-use crate::error::{Error, ErrorCode, Position, Result};
+use self::read::Input;
 
 /// Parse a JSON5 string and map it to a type implementing [`Deserialize`].
 ///
@@ -36,20 +50,200 @@ use crate::error::{Error, ErrorCode, Position, Result};
 /// # Errors
 /// Fails if the JSON5 is malformed or we can't map it to a `T`.
 pub fn from_str<'de, T: Deserialize<'de>>(input: &'de str) -> Result<T> {
-    let mut deserializer = Deserializer::from_str(input);
+    from_deserializer(Deserializer::from_str(input))
+}
+
+/// Parse JSON5 from a byte slice and map it to a type implementing [`Deserialize`].
+///
+/// # Example
+/// ```
+/// let ids: Vec<u32> = json5::from_slice(b"[1, 0x2, 3,]")?;
+///
+/// assert_eq!(ids, [1, 2, 3]);
+/// # Ok::<(), json5::Error>(())
+/// ```
+///
+/// # Errors
+/// Fails if the input isn't valid UTF-8, if the JSON5 is malformed, or if we can't map it to a
+/// `T`.
+pub fn from_slice<'de, T: Deserialize<'de>>(input: &'de [u8]) -> Result<T> {
+    from_deserializer(Deserializer::from_slice(input)?)
+}
+
+/// Parse JSON5 from an [`io::Read`] and map it to a type implementing [`Deserialize`].
+///
+/// The input is read a chunk at a time into a buffer as it's parsed, and the buffer is trimmed as
+/// parsing moves on, so a large file is never held in memory whole. Since reads go through that
+/// buffer, there's no need to wrap the reader in a [`BufReader`](io::BufReader). Strings can't be
+/// borrowed from the input, so `T` must be [`DeserializeOwned`].
+///
+/// With [`DuplicateKeys::LastWins`] each object is held in memory while it's deserialized, since
+/// it's scanned for duplicate keys first.
+///
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+///
+/// let reader = std::io::Cursor::new("{ foo: 'bar' }");
+/// let map: HashMap<String, String> = json5::from_reader(reader)?;
+///
+/// assert_eq!(map["foo"], "bar");
+/// # Ok::<(), json5::Error>(())
+/// ```
+///
+/// # Errors
+/// Fails if reading fails, if the input isn't valid UTF-8, if the JSON5 is malformed, or if we
+/// can't map it to a `T`.
+pub fn from_reader<R: io::Read, T: DeserializeOwned>(reader: R) -> Result<T> {
+    from_deserializer(Deserializer::from_reader(reader))
+}
+
+/// Check that a string is well-formed JSON5, returning every syntax error found rather than just
+/// the first. Each error has a position and span, so this is suitable for highlighting mistakes in
+/// an editor. See [`Deserializer::validate`] for details.
+///
+/// # Example
+/// ```
+/// use json5::ErrorCode;
+///
+/// let errors = json5::validate("{ a: tru, b: [1 2], c: 'ok' }");
+/// let codes = errors.iter().map(json5::Error::code).collect::<Vec<_>>();
+///
+/// assert_eq!(codes, [Some(ErrorCode::ExpectedBool), Some(ErrorCode::ExpectedComma)]);
+/// assert_eq!(errors[1].offset(), Some(16));
+/// ```
+#[must_use]
+pub fn validate(input: &str) -> Vec<Error> {
+    Deserializer::from_str(input).validate()
+}
+
+fn from_deserializer<'de, T: Deserialize<'de>>(mut deserializer: Deserializer<'de>) -> Result<T> {
     let t = T::deserialize(&mut deserializer)?;
-    deserializer.skip_whitespace()?;
-    match deserializer.peek() {
-        Some((offset, _)) => Err(deserializer.err_at(offset, ErrorCode::TrailingCharacters)),
-        None => Ok(t),
-    }
+    deserializer.end()?;
+    Ok(t)
 }
 
 /// A deserializer that knows how to parse JSON5 and map it on to types implementing
 /// [`Deserialize`].
 pub struct Deserializer<'de> {
-    input: &'de str,
-    char_indices: Peekable<CharIndices<'de>>,
+    input: Input<'de>,
+    // The byte offset of the next char in the input.
+    offset: usize,
+    // The number of arrays and objects we're currently inside.
+    depth: usize,
+    options: ParseOptions,
+    // While validating, errors we can carry on from are collected here instead of being returned.
+    recovered: Option<Vec<Error>>,
+    // For DuplicateKeys::LastWins, the offset of the last occurrence of each key of the objects
+    // we've scanned but not yet deserialized, by the offset just after their opening brace.
+    last_offsets: HashMap<usize, HashMap<String, usize>>,
+    // When reading, the offsets we might still need the position of (or the key at) after
+    // discarding the input there, in order.
+    marks: Vec<Mark>,
+    // When reading, an offset we'll come back to, so mustn't discard the input after.
+    hold: Option<usize>,
+}
+
+// An offset marked with `Deserializer::mark`. The position (and for keys, the key) is filled in
+// when the input there is discarded.
+struct Mark {
+    offset: usize,
+    kind: MarkKind,
+    position: Option<Position>,
+    char: Option<char>,
+    key: Option<String>,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum MarkKind {
+    // The opening bracket of a collection we're inside.
+    Collection,
+    // The key of the current entry of an object.
+    Key,
+    // The first occurrence of a key, for DuplicateKeys::Error.
+    FirstKey,
+    // The start of any other value errors may be positioned at.
+    Value,
+}
+
+/// Options controlling how a [`Deserializer`] parses its input.
+///
+/// # Example
+/// ```
+/// use json5::{Deserializer, Dialect, ErrorCode, ParseOptions};
+/// use serde::Deserialize;
+///
+/// let mut de = Deserializer::from_str("{ unquoted: true }").with_options(ParseOptions {
+///     dialect: Dialect::Json,
+///     ..ParseOptions::default()
+/// });
+/// let err = serde_json::Value::deserialize(&mut de).unwrap_err();
+///
+/// assert_eq!(err.code(), Some(ErrorCode::UnquotedKeyInJson));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ParseOptions {
+    /// The syntax to accept. Defaults to [`Dialect::Json5`].
+    pub dialect: Dialect,
+    /// The maximum number of arrays and objects that can be nested inside each other, beyond which
+    /// parsing fails with [`ErrorCode::RecursionLimitExceeded`]. This protects against stack
+    /// overflows on malicious input. Set to `None` to remove the limit for trusted input. Defaults
+    /// to `Some(128)`.
+    pub max_depth: Option<usize>,
+    /// What to do when an object contains the same key more than once. Defaults to
+    /// [`DuplicateKeys::Allow`].
+    pub duplicate_keys: DuplicateKeys,
+    /// How byte arrays are written in the input. Defaults to [`ByteEncoding::Hex`].
+    pub bytes: ByteEncoding,
+    /// Accept byte arrays written either as an array of integers or as a string, whatever
+    /// [`bytes`](ParseOptions::bytes) is: one starting with `[` is read as an array, and a string
+    /// is read in the encoding given by `bytes` (hex if that's [`ByteEncoding::Array`]). Defaults to
+    /// `false`.
+    ///
+    /// Strings aren't guessed between hex and base64, since many are valid as both: e.g. `"AAAA"`
+    /// is `[0xaa, 0xaa]` as hex and `[0, 0, 0]` as base64. Set `bytes` to say which to expect.
+    pub detect_bytes: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            dialect: Dialect::default(),
+            max_depth: Some(128),
+            duplicate_keys: DuplicateKeys::default(),
+            bytes: ByteEncoding::default(),
+            detect_bytes: false,
+        }
+    }
+}
+
+/// What to do when an object contains the same key more than once. Keys are compared after
+/// unescaping, so `a`, `'a'`, and `"\u0061"` are all the same key.
+///
+/// This is enforced while parsing, so it applies whatever type we're deserializing in to.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum DuplicateKeys {
+    /// Pass every entry on to the type we're deserializing in to, which decides what to do. e.g.
+    /// for a [`HashMap`] the last entry wins, for a struct it's an error.
+    #[default]
+    Allow,
+    /// Fail with [`ErrorCode::DuplicateKey`] at the second occurrence of the key.
+    Error,
+    /// Keep the first entry with a given key and skip the others.
+    FirstWins,
+    /// Keep the last entry with a given key and skip the others.
+    LastWins,
+}
+
+/// The flavour of JSON to read (or write).
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Dialect {
+    /// JSON5 as described by <https://spec.json5.org/>.
+    #[default]
+    Json5,
+    /// Plain JSON as described by <https://www.json.org/>. Each JSON5 extension is rejected with
+    /// its own [`ErrorCode`] (e.g. [`ErrorCode::CommentInJson`]).
+    Json,
 }
 
 impl<'de> Deserializer<'de> {
@@ -60,28 +254,201 @@ impl<'de> Deserializer<'de> {
     )]
     #[must_use]
     pub fn from_str(input: &'de str) -> Self {
+        Self::new(Input::from_str(input))
+    }
+
+    /// Construct a deserializer that will read from the given [`io::Read`]. The input is read a
+    /// chunk at a time as it's parsed, and only the part of it that's still needed is kept.
+    #[must_use]
+    pub fn from_reader<R: io::Read + 'de>(reader: R) -> Self {
+        Self::new(Input::from_reader(reader))
+    }
+
+    fn new(input: Input<'de>) -> Self {
         Self {
             input,
-            char_indices: input.char_indices().peekable(),
+            offset: 0,
+            depth: 0,
+            options: ParseOptions::default(),
+            recovered: None,
+            last_offsets: HashMap::new(),
+            marks: Vec::new(),
+            hold: None,
+        }
+    }
+
+    /// Construct a deserializer that will read from the given JSON5 bytes.
+    ///
+    /// # Errors
+    /// Fails if the input isn't valid UTF-8.
+    pub fn from_slice(input: &'de [u8]) -> Result<Self> {
+        match std::str::from_utf8(input) {
+            Ok(input) => Ok(Self::from_str(input)),
+            Err(err) => Err(utf8_err(input, &err)),
+        }
+    }
+
+    /// Parse the input according to the given options, rather than the defaults.
+    #[must_use]
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Check that there's nothing but whitespace and comments left in the input. This should be
+    /// called after deserializing a value, unless trailing characters are acceptable.
+    ///
+    /// # Errors
+    /// Fails if there are trailing characters, or if reading the rest of the input fails.
+    pub fn end(&mut self) -> Result<()> {
+        self.skip_whitespace()?;
+        match self.peek() {
+            Some((offset, _)) => Err(self.err_at(offset, ErrorCode::TrailingCharacters)),
+            None => self.input.error().cloned().map_or(Ok(()), Err),
+        }
+    }
+
+    /// Parse the input without mapping it to a type, and return every syntax error found (in the
+    /// order they occur) rather than stopping at the first. An empty vec means the input is
+    /// well-formed.
+    ///
+    /// After an error we skip ahead to the next `,`, `:`, `]` or `}` and carry on from there, so
+    /// each mistake is reported once, and mistakes later in the input are still found. Violations
+    /// of [`Dialect::Json`] and (if [`DuplicateKeys::Error`] is set) duplicate keys are reported
+    /// too. Exceeding [`ParseOptions::max_depth`] stops validation.
+    #[must_use]
+    pub fn validate(mut self) -> Vec<Error> {
+        self.recovered = Some(Vec::new());
+        if let Err(err) = self
+            .recover_value(&mut Vec::new())
+            .and_then(|()| self.end())
+        {
+            self.recover(err);
+        }
+        let mut errors = self.recovered.take().unwrap_or_default();
+        errors.sort_by_key(Error::offset);
+        errors
+    }
+
+    /// Turn this deserializer into an iterator over a sequence of JSON5 values, separated by
+    /// whitespace or comments. Each value is yielded along with the byte offset it starts at.
+    ///
+    /// # Example
+    /// ```
+    /// use json5::Deserializer;
+    ///
+    /// let input = "{ id: 1 } // first\n{ id: 2 }\n";
+    /// let values = Deserializer::from_str(input)
+    ///     .into_iter::<json5::Value>()
+    ///     .map(|res| res.map(|(offset, value)| (offset, value["id"].as_u64())))
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    ///
+    /// assert_eq!(values, [(0, Some(1)), (19, Some(2))]);
+    /// # Ok::<(), json5::Error>(())
+    /// ```
+    #[expect(
+        clippy::should_implement_trait,
+        reason = "Serde convention: https://docs.rs/serde_json/latest/serde_json/struct.Deserializer.html#method.into_iter"
+    )]
+    #[must_use]
+    pub fn into_iter<T: Deserialize<'de>>(self) -> StreamDeserializer<'de, T> {
+        StreamDeserializer {
+            de: self,
+            failed: false,
+            output: PhantomData,
         }
     }
 }
 
+/// An iterator over a sequence of JSON5 values, constructed with [`Deserializer::into_iter`].
+///
+/// Yields `(offset, value)` pairs, where `offset` is the byte offset the value starts at. Stops
+/// after the last value, or after the first error.
+pub struct StreamDeserializer<'de, T> {
+    de: Deserializer<'de>,
+    failed: bool,
+    output: PhantomData<T>,
+}
+
+impl<'de, T: Deserialize<'de>> StreamDeserializer<'de, T> {
+    fn next_value(&mut self) -> Result<Option<(usize, T)>> {
+        self.de.skip_whitespace()?;
+        let Some((start, _)) = self.de.peek() else {
+            return self.de.input.error().cloned().map_or(Ok(None), Err);
+        };
+        self.de.discard();
+        let t = T::deserialize(&mut self.de)?;
+
+        // Values which don't end in a delimiter (e.g. true, 1) must be separated from the next
+        // value, otherwise we'd accept things like "truefalse".
+        let last = self.de.input.char_before(self.de.offset);
+        if !matches!(last, Some(']' | '}' | '"' | '\''))
+            && let Some((offset, c)) = self.de.peek()
+            && !crate::char::is_json5_whitespace(c)
+            && c != '/'
+        {
+            return Err(self.de.err_at(offset, ErrorCode::TrailingCharacters));
+        }
+
+        Ok(Some((start, t)))
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Iterator for StreamDeserializer<'de, T> {
+    type Item = Result<(usize, T)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let res = self.next_value();
+        self.failed = res.is_err();
+        res.transpose()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> FusedIterator for StreamDeserializer<'de, T> {}
+
+// Positions the error at the first invalid byte, and spans it over the invalid sequence.
+fn utf8_err(input: &[u8], err: &std::str::Utf8Error) -> Error {
+    let offset = err.valid_up_to();
+    let valid = std::str::from_utf8(&input[..offset]).expect("valid up to here");
+    let len = err.error_len().unwrap_or(input.len() - offset);
+    Error::new_at(Position::from_offset(offset, valid), ErrorCode::InvalidUtf8)
+        .with_span(offset..offset + len)
+}
+
 impl<'de> Deserializer<'de> {
-    fn next(&mut self) -> Option<(usize, char)> {
-        self.char_indices.next()
+    pub(crate) fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub(crate) fn next(&mut self) -> Option<(usize, char)> {
+        let next = self.peek()?;
+        self.offset += next.1.len_utf8();
+        Some(next)
     }
 
-    fn peek(&mut self) -> Option<(usize, char)> {
-        self.char_indices.peek().copied()
+    pub(crate) fn peek(&mut self) -> Option<(usize, char)> {
+        self.input
+            .read_char_at(self.offset)
+            .map(|c| (self.offset, c))
     }
 
     fn next_or(&mut self, eof: ErrorCode) -> Result<(usize, char)> {
-        self.next().ok_or_else(|| Error::new(eof))
+        self.next().ok_or_else(|| self.eof(eof))
     }
 
     fn peek_or(&mut self, eof: ErrorCode) -> Result<(usize, char)> {
-        self.peek().ok_or_else(|| Error::new(eof))
+        self.peek().ok_or_else(|| self.eof(eof))
+    }
+
+    // The error for reaching the end of the input. If reading failed, that's the real error.
+    fn eof(&self, code: ErrorCode) -> Error {
+        self.input
+            .error()
+            .cloned()
+            .unwrap_or_else(|| Error::new(code))
     }
 
     fn expect_char(
@@ -115,6 +482,15 @@ impl<'de> Deserializer<'de> {
         Ok(offset)
     }
 
+    // Parses a token with the given function after skipping whitespace. EOF errors, which don't
+    // know where they occured, are positioned at the start of the token (which is the end of the
+    // input if the token is missing altogether).
+    fn parse_token<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.skip_whitespace()?;
+        let start = self.offset;
+        parse(self).map_err(|err| self.with_position(err, start))
+    }
+
     fn expect_collection_end(
         &mut self,
         close: char,
@@ -128,7 +504,7 @@ impl<'de> Deserializer<'de> {
             ',' => {
                 self.skip_whitespace()?;
                 self.expect_char(close, eof, unexpected)?;
-                Ok(())
+                self.reject_in_json(offset, ErrorCode::TrailingCommaInJson)
             }
             _ => Err(self.err_at(offset, unexpected)),
         }
@@ -136,14 +512,19 @@ impl<'de> Deserializer<'de> {
 
     // https://spec.json5.org/#white-space
     fn skip_whitespace(&mut self) -> Result<()> {
-        while let Some((_, c)) = self.peek() {
+        while let Some((offset, c)) = self.peek() {
             match c {
                 _ if crate::char::is_json5_whitespace(c) => {
+                    if !matches!(c, '\u{0009}' | '\u{000A}' | '\u{000D}' | '\u{0020}') {
+                        self.reject_in_json(offset, ErrorCode::WhitespaceInJson)?;
+                    }
                     self.next();
                 }
                 '/' => {
+                    self.reject_in_json(offset, ErrorCode::CommentInJson)?;
                     self.next();
-                    self.skip_comment()?;
+                    self.skip_comment()
+                        .map_err(|err| self.with_position(err, offset))?;
                 }
                 _ => {
                     break;
@@ -180,36 +561,36 @@ impl<'de> Deserializer<'de> {
     }
 
     fn parse_null(&mut self) -> Result<usize> {
-        self.skip_whitespace()?;
-        let (offset, _) = self.peek_or(ErrorCode::EofParsingNull)?;
-        self.expect_str("null", ErrorCode::EofParsingNull, ErrorCode::ExpectedNull)?;
-        Ok(offset)
+        self.parse_token(|de| {
+            de.expect_str("null", ErrorCode::EofParsingNull, ErrorCode::ExpectedNull)
+        })
     }
 
     fn parse_bool(&mut self) -> Result<(usize, bool)> {
-        self.skip_whitespace()?;
-
-        match self.next_or(ErrorCode::EofParsingBool)? {
+        self.parse_token(|de| match de.next_or(ErrorCode::EofParsingBool)? {
             (offset, 't') => {
-                self.expect_str("rue", ErrorCode::EofParsingBool, ErrorCode::ExpectedBool)?;
+                de.expect_str("rue", ErrorCode::EofParsingBool, ErrorCode::ExpectedBool)?;
                 Ok((offset, true))
             }
             (offset, 'f') => {
-                self.expect_str("alse", ErrorCode::EofParsingBool, ErrorCode::ExpectedBool)?;
+                de.expect_str("alse", ErrorCode::EofParsingBool, ErrorCode::ExpectedBool)?;
                 Ok((offset, false))
             }
-            (offset, _) => Err(self.err_at(offset, ErrorCode::ExpectedBool)),
-        }
+            (offset, _) => Err(de.err_at(offset, ErrorCode::ExpectedBool)),
+        })
     }
 
     // https://spec.json5.org/#numbers
     fn parse_number(&mut self) -> Result<(usize, NumberResult)> {
-        self.skip_whitespace()?;
+        self.parse_token(Self::parse_number_token)
+    }
 
+    fn parse_number_token(&mut self) -> Result<(usize, NumberResult)> {
         let (start, _) = self.peek_or(ErrorCode::EofParsingNumber)?;
 
         let neg = match self.peek_or(ErrorCode::EofParsingNumber)? {
-            (_, '+') => {
+            (offset, '+') => {
+                self.reject_in_json(offset, ErrorCode::PlusSignInJson)?;
                 self.next();
                 false
             }
@@ -222,6 +603,7 @@ impl<'de> Deserializer<'de> {
 
         match self.next_or(ErrorCode::EofParsingNumber)? {
             (_, 'I') => {
+                self.reject_in_json(start, ErrorCode::InfinityInJson)?;
                 self.expect_str(
                     "nfinity",
                     ErrorCode::EofParsingNumber,
@@ -234,6 +616,7 @@ impl<'de> Deserializer<'de> {
                 }
             }
             (_, 'N') => {
+                self.reject_in_json(start, ErrorCode::NanInJson)?;
                 self.expect_str("aN", ErrorCode::EofParsingNumber, ErrorCode::ExpectedNumber)?;
                 if neg {
                     Ok((start, NumberResult::F64(-f64::NAN)))
@@ -243,6 +626,7 @@ impl<'de> Deserializer<'de> {
             }
             (_, '0') => match self.peek() {
                 Some((_, 'x' | 'X')) => {
+                    self.reject_in_json(start, ErrorCode::HexNumberInJson)?;
                     self.next();
                     self.parse_hex_number(neg, start).map(|n| (start, n))
                 }
@@ -252,7 +636,12 @@ impl<'de> Deserializer<'de> {
                 Some((_, '0'..='9')) => Err(self.err_at(start, ErrorCode::LeadingZero)),
                 _ => Ok((start, NumberResult::U128(0))),
             },
-            (offset, '.' | '1'..='9') => self
+            (offset, '.') => {
+                self.reject_in_json(offset, ErrorCode::LeadingDecimalPointInJson)?;
+                self.parse_decimal_number(neg, start, offset)
+                    .map(|n| (start, n))
+            }
+            (offset, '1'..='9') => self
                 .parse_decimal_number(neg, start, offset)
                 .map(|n| (start, n)),
             (offset, _) => Err(self.err_at(offset, ErrorCode::ExpectedNumber)),
@@ -274,7 +663,14 @@ impl<'de> Deserializer<'de> {
             self.next();
             offset = o;
         }
-        if self.input[start..=offset].contains(['.', 'e', 'E']) {
+        let number = self.input.slice(start, offset + 1);
+        let is_float = number.contains(['.', 'e', 'E']);
+        if let Some(point) = number.find('.')
+            && !number[point + 1..].starts_with(|c: char| c.is_ascii_digit())
+        {
+            self.reject_in_json(start + point, ErrorCode::TrailingDecimalPointInJson)?;
+        }
+        if is_float {
             // https://doc.rust-lang.org/std/primitive.f64.html#method.from_str
             Ok(NumberResult::F64(self.parse_from_str(start, offset)?))
         } else if neg {
@@ -291,9 +687,10 @@ impl<'de> Deserializer<'de> {
         N: FromStr,
         N::Err: Display,
     {
-        self.input[start..=offset]
+        self.input
+            .slice(start, offset + 1)
             .parse()
-            .map_err(|err: N::Err| self.custom_err_at(start, err))
+            .map_err(|err: N::Err| self.custom_err_in(start..offset + 1, err))
     }
 
     fn parse_hex_number(&mut self, neg: bool, start: usize) -> Result<NumberResult> {
@@ -320,9 +717,9 @@ impl<'de> Deserializer<'de> {
             if n == 0x8000_0000_0000_0000_0000_0000_0000_0000 {
                 Ok(NumberResult::I128(i128::MIN))
             } else {
-                Ok(NumberResult::I128(
-                    -i128::try_from(n).map_err(|err| self.custom_err_at(start, err))?,
-                ))
+                Ok(NumberResult::I128(-i128::try_from(n).map_err(|err| {
+                    self.custom_err_in(start..self.offset, err)
+                })?))
             }
         } else {
             Ok(NumberResult::U128(n))
@@ -331,14 +728,17 @@ impl<'de> Deserializer<'de> {
 
     // https://spec.json5.org/#strings
     fn parse_string(&mut self) -> Result<(usize, StringResult<'de>)> {
-        self.skip_whitespace()?;
-
-        let (offset, c) = self.next_or(ErrorCode::EofParsingString)?;
-        if c == '"' || c == '\'' {
-            self.parse_string_characters(c).map(|s| (offset, s))
-        } else {
-            Err(self.err_at(offset, ErrorCode::ExpectedString))
-        }
+        self.parse_token(|de| {
+            let (offset, c) = de.next_or(ErrorCode::EofParsingString)?;
+            if c == '"' || c == '\'' {
+                if c == '\'' {
+                    de.reject_in_json(offset, ErrorCode::SingleQuotesInJson)?;
+                }
+                de.parse_string_characters(c).map(|s| (offset, s))
+            } else {
+                Err(de.err_at(offset, ErrorCode::ExpectedString))
+            }
+        })
     }
 
     fn parse_string_characters(&mut self, delimiter: char) -> Result<StringResult<'de>> {
@@ -352,17 +752,22 @@ impl<'de> Deserializer<'de> {
                 if let Some(owned) = owned {
                     return Ok(StringResult::Owned(owned));
                 }
-                return Ok(StringResult::Borrowed(&self.input[start..offset]));
+                return Ok(self.slice(start, offset));
             } else if c == '\u{000A}' || c == '\u{000D}' {
                 // LineTerminator is forbidden except U+2028 and U+2029 are explicitly allowed.
                 return Err(self.err_at(offset, ErrorCode::LineTerminatorInString));
             } else if c == '\\' {
-                let owned = owned.get_or_insert(self.input[start..offset].to_owned());
+                let owned = owned.get_or_insert(self.input.slice(start, offset).to_owned());
                 if let Some(c) = self.parse_escape_sequence(offset)? {
                     owned.push(c);
                 }
-            } else if let Some(owned) = &mut owned {
-                owned.push(c);
+            } else {
+                if c < '\u{0020}' {
+                    self.reject_in_json(offset, ErrorCode::ControlCharacterInJson)?;
+                }
+                if let Some(owned) = &mut owned {
+                    owned.push(c);
+                }
             }
         }
     }
@@ -370,6 +775,13 @@ impl<'de> Deserializer<'de> {
     // https://262.ecma-international.org/5.1/#sec-7.8.4
     fn parse_escape_sequence(&mut self, offset: usize) -> Result<Option<char>> {
         let (_, c) = self.next_or(ErrorCode::EofParsingEscapeSequence)?;
+        if !matches!(c, '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' | 'u') {
+            if crate::char::is_json5_line_terminator(c) {
+                self.reject_in_json(offset, ErrorCode::LineContinuationInJson)?;
+            } else {
+                self.reject_in_json(offset, ErrorCode::EscapeSequenceInJson)?;
+            }
+        }
         match c {
             // LineTerminatorSequence
             _ if crate::char::is_json5_line_terminator(c) => {
@@ -389,12 +801,12 @@ impl<'de> Deserializer<'de> {
 
             '0' => {
                 if self.peek().is_some_and(|(_, c)| c.is_ascii_digit()) {
-                    return Err(self.err_at(offset, ErrorCode::InvalidEscapeSequence));
+                    return Err(self.err_in(offset..self.offset, ErrorCode::InvalidEscapeSequence));
                 }
                 Ok(Some('\u{0000}'))
             }
 
-            '1'..'9' => Err(self.err_at(offset, ErrorCode::InvalidEscapeSequence)),
+            '1'..'9' => Err(self.err_in(offset..self.offset, ErrorCode::InvalidEscapeSequence)),
 
             'x' => Ok(Some(self.parse_hex_escape_sequence(offset)?)),
 
@@ -406,7 +818,7 @@ impl<'de> Deserializer<'de> {
 
     fn parse_hex_escape_sequence(&mut self, offset: usize) -> Result<char> {
         char::try_from(self.parse_escape_sequence_digits(offset, 2)?)
-            .map_err(|err| self.custom_err_at(offset, err))
+            .map_err(|err| self.custom_err_in(offset..self.offset, err))
     }
 
     fn parse_unicode_escape_sequence(&mut self, offset: usize) -> Result<char> {
@@ -426,18 +838,18 @@ impl<'de> Deserializer<'de> {
         let b = self.parse_escape_sequence_digits(offset, 4)?;
 
         let mut chars = char::decode_utf16([
-            u16::try_from(a).map_err(|err| self.custom_err_at(offset, err))?,
-            u16::try_from(b).map_err(|err| self.custom_err_at(offset, err))?,
+            u16::try_from(a).map_err(|err| self.custom_err_in(offset..self.offset, err))?,
+            u16::try_from(b).map_err(|err| self.custom_err_in(offset..self.offset, err))?,
         ]);
         let c = chars
             .next()
-            .ok_or_else(|| self.err_at(offset, ErrorCode::InvalidEscapeSequence))?
-            .map_err(|err| self.custom_err_at(offset, err))?;
+            .ok_or_else(|| self.err_in(offset..self.offset, ErrorCode::InvalidEscapeSequence))?
+            .map_err(|err| self.custom_err_in(offset..self.offset, err))?;
 
         if chars.next().is_none() {
             Ok(c)
         } else {
-            Err(self.err_at(offset, ErrorCode::InvalidEscapeSequence))
+            Err(self.err_in(offset..self.offset, ErrorCode::InvalidEscapeSequence))
         }
     }
 
@@ -446,7 +858,7 @@ impl<'de> Deserializer<'de> {
         for _ in 0..length {
             let (_, c) = self.next_or(ErrorCode::EofParsingEscapeSequence)?;
             if !c.is_ascii_hexdigit() {
-                return Err(self.err_at(offset, ErrorCode::InvalidEscapeSequence));
+                return Err(self.err_in(offset..self.offset, ErrorCode::InvalidEscapeSequence));
             }
             value = value * 16 + c.to_digit(16).expect("c.is_ascii_hexdigit");
         }
@@ -454,12 +866,16 @@ impl<'de> Deserializer<'de> {
     }
 
     // https://spec.json5.org/#objects
-    fn parse_key(&mut self) -> Result<(usize, StringResult<'de>)> {
+    pub(crate) fn parse_key(&mut self) -> Result<(usize, StringResult<'de>)> {
         self.skip_whitespace()?;
 
         match self.peek_or(ErrorCode::EofParsingObject)? {
             (_, '"' | '\'') => self.parse_string(),
-            (offset, _) => self.parse_identifier().map(|i| (offset, i)),
+            (offset, _) => {
+                self.reject_in_json(offset, ErrorCode::UnquotedKeyInJson)?;
+                self.parse_token(Self::parse_identifier)
+                    .map(|i| (offset, i))
+            }
         }
     }
 
@@ -473,7 +889,7 @@ impl<'de> Deserializer<'de> {
 
             if c == '\\' {
                 self.next();
-                let owned = owned.get_or_insert(self.input[start..offset].to_owned());
+                let owned = owned.get_or_insert(self.input.slice(start, offset).to_owned());
                 self.expect_char(
                     'u',
                     ErrorCode::EofParsingIdentifier,
@@ -482,10 +898,10 @@ impl<'de> Deserializer<'de> {
                 let c = self.parse_unicode_escape_sequence(offset)?;
                 if offset == start {
                     if !crate::char::is_json5_identifier_start(c) {
-                        return Err(self.err_at(offset, ErrorCode::ExpectedIdentifier));
+                        return Err(self.err_in(offset..self.offset, ErrorCode::ExpectedIdentifier));
                     }
                 } else if !crate::char::is_json5_identifier(c) {
-                    return Err(self.err_at(offset, ErrorCode::ExpectedIdentifier));
+                    return Err(self.err_in(offset..self.offset, ErrorCode::ExpectedIdentifier));
                 }
                 owned.push(c);
                 continue;
@@ -498,7 +914,7 @@ impl<'de> Deserializer<'de> {
             } else if !crate::char::is_json5_identifier(c) {
                 return Ok(match owned {
                     Some(owned) => StringResult::Owned(owned),
-                    None => StringResult::Borrowed(&self.input[start..offset]),
+                    None => self.slice(start, offset),
                 });
             }
 
@@ -509,33 +925,541 @@ impl<'de> Deserializer<'de> {
         }
     }
 
-    fn decode_hex(&self, offset: usize, s: &str) -> Result<Vec<u8>> {
-        let mut chars = s.chars();
+    // Borrow from the input if we can, otherwise copy.
+    fn slice(&self, start: usize, end: usize) -> StringResult<'de> {
+        match self.input.borrowed() {
+            Some(input) => StringResult::Borrowed(&input[start..end]),
+            None => StringResult::Owned(self.input.slice(start, end).to_owned()),
+        }
+    }
+
+    // Decodes the bytes in the string (or key) starting at the given offset, which we've just
+    // parsed.
+    fn decode_bytes(&self, offset: usize, s: &str) -> Result<Vec<u8>> {
+        bytes::decode(s, self.options.bytes).map_err(|i| {
+            // If the string has no escapes we can point at the offending character, otherwise we
+            // point at the whole string.
+            let start =
+                offset + usize::from(matches!(self.input.char_at(offset), Some('"' | '\'')));
+            if self.input.get(start..start + s.len()) == Some(s) {
+                self.err_at(start + i, ErrorCode::InvalidBytes)
+            } else {
+                self.err_in(offset..self.offset, ErrorCode::InvalidBytes)
+            }
+        })
+    }
+
+    // Parses an array of integers from 0 to 255.
+    fn parse_byte_array(&mut self) -> Result<(usize, Vec<u8>)> {
+        self.parse_token(Self::parse_byte_array_token)
+    }
+
+    fn parse_byte_array_token(&mut self) -> Result<(usize, Vec<u8>)> {
+        let offset = self.expect_char(
+            '[',
+            ErrorCode::EofParsingArray,
+            ErrorCode::ExpectedOpeningBracket,
+        )?;
+        self.enter_collection(offset)?;
         let mut bytes = Vec::new();
-        while let Some(a) = chars.next() {
-            match a
-                .to_digit(16)
-                .and_then(|a| chars.next().and_then(|b| b.to_digit(16)).map(|b| (a, b)))
+        loop {
+            self.skip_whitespace()?;
+            if self.peek().is_some_and(|(_, c)| c == ']') {
+                break;
+            }
+            if !bytes.is_empty() {
+                let comma =
+                    self.expect_char(',', ErrorCode::EofParsingArray, ErrorCode::ExpectedComma)?;
+                self.skip_whitespace()?;
+                if self.peek().is_some_and(|(_, c)| c == ']') {
+                    self.reject_in_json(comma, ErrorCode::TrailingCommaInJson)?;
+                    break;
+                }
+            }
+            let (start, n) = self.parse_number()?;
+            let byte = match n {
+                NumberResult::U128(n) => u8::try_from(n).ok(),
+                NumberResult::I128(_) | NumberResult::F64(_) => None,
+            };
+            let Some(byte) = byte else {
+                return Err(self.err_in(start..self.offset, ErrorCode::InvalidBytes));
+            };
+            bytes.push(byte);
+        }
+        self.expect_collection_end(
+            ']',
+            ErrorCode::EofParsingArray,
+            ErrorCode::ExpectedClosingBracket,
+        )?;
+        self.leave_collection();
+        Ok((offset, bytes))
+    }
+
+    // Skips over a value for DuplicateKeys::LastWins, recording the offset of the last occurrence
+    // of each key of every object inside it. Each object is then scanned only once however deeply
+    // it's nested, rather than again by each object it's inside.
+    fn scan_value(&mut self) -> Result<()> {
+        self.skip_whitespace()?;
+        let Some((offset, c @ ('[' | '{'))) = self.peek() else {
+            IgnoredAny::deserialize(&mut *self)?;
+            return Ok(());
+        };
+        self.next();
+        self.enter_collection(offset)?;
+        if c == '[' {
+            let mut first = true;
+            while self.scan_separator(']', &mut first, ErrorCode::EofParsingArray)? {
+                self.scan_value()?;
+            }
+            self.expect_collection_end(
+                ']',
+                ErrorCode::EofParsingArray,
+                ErrorCode::ExpectedClosingBracket,
+            )?;
+        } else {
+            let start = self.offset;
+            let last_offsets = self.scan_object()?;
+            self.last_offsets.insert(start, last_offsets);
+            self.expect_collection_end(
+                '}',
+                ErrorCode::EofParsingObject,
+                ErrorCode::ExpectedClosingBrace,
+            )?;
+        }
+        self.leave_collection();
+        Ok(())
+    }
+
+    // Scans the entries of the object whose opening brace we've just passed, returning the offset
+    // of the last occurrence of each key. Stops before the closing brace.
+    fn scan_object(&mut self) -> Result<HashMap<String, usize>> {
+        let mut last_offsets = HashMap::new();
+        let mut first = true;
+        while self.scan_separator('}', &mut first, ErrorCode::EofParsingObject)? {
+            let (offset, key) = self.parse_key()?;
+            last_offsets.insert(key.to_owned(), offset);
+            self.skip_whitespace()?;
+            self.expect_char(':', ErrorCode::EofParsingObject, ErrorCode::ExpectedColon)?;
+            self.scan_value()?;
+        }
+        Ok(last_offsets)
+    }
+
+    // Skips the comma before the next element while scanning. Returns false at the end of the
+    // collection.
+    fn scan_separator(&mut self, close: char, first: &mut bool, eof: ErrorCode) -> Result<bool> {
+        self.skip_whitespace()?;
+        if self.peek().is_some_and(|(_, c)| c == close) {
+            return Ok(false);
+        }
+        if !std::mem::take(first) {
+            self.expect_char(',', eof, ErrorCode::ExpectedComma)?;
+            self.skip_whitespace()?;
+        }
+        Ok(self.peek().is_some_and(|(_, c)| c != close))
+    }
+
+    pub(crate) fn enter_collection(&mut self, offset: usize) -> Result<()> {
+        self.mark(offset, MarkKind::Collection);
+        self.depth += 1;
+        if self.options.max_depth.is_some_and(|max| self.depth > max) {
+            return Err(self.err_at(offset, ErrorCode::RecursionLimitExceeded));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn leave_collection(&mut self) {
+        self.depth -= 1;
+        if let Some(i) = self
+            .marks
+            .iter()
+            .rposition(|mark| mark.kind == MarkKind::Collection)
+        {
+            self.marks.truncate(i);
+        }
+    }
+
+    // When reading, records an offset we might need the position of (or the key at) after the
+    // input there is discarded. Marks are dropped when we leave the collection they're in.
+    fn mark(&mut self, offset: usize, kind: MarkKind) {
+        if !self.input.is_reading() {
+            return;
+        }
+        let mark = Mark {
+            offset,
+            kind,
+            position: None,
+            char: None,
+            key: None,
+        };
+        match self.marks.last_mut() {
+            // Only the current entry's key is needed.
+            Some(last)
+                if last.kind == MarkKind::Key
+                    && matches!(kind, MarkKind::Key | MarkKind::FirstKey) =>
             {
-                Some((a, b)) => {
-                    bytes.push(u8::try_from(a * 16 + b).expect("two hex digits fit in a u8"));
+                *last = mark;
+            }
+            Some(last) if last.offset == offset && kind != MarkKind::Collection => {}
+            _ => self.marks.push(mark),
+        }
+    }
+
+    fn mark_at(&self, offset: usize) -> Option<&Mark> {
+        let i = self
+            .marks
+            .binary_search_by_key(&offset, |mark| mark.offset)
+            .ok()?;
+        Some(&self.marks[i])
+    }
+
+    // When reading, discards the input before the current offset (or the hold, if that's before
+    // it), once there's enough of it to be worth it. Marks there are filled in first.
+    fn discard(&mut self) {
+        let keep = self.hold.map_or(self.offset, |hold| hold.min(self.offset));
+        let Some(keep) = self.input.discardable(keep) else {
+            return;
+        };
+        let start = self.input.start();
+        let from = self.marks.partition_point(|mark| mark.offset < start);
+        let to = self.marks.partition_point(|mark| mark.offset < keep);
+        let (mut prev, mut position) = (start, self.input.position(start));
+        for i in from..to {
+            let (offset, kind) = (self.marks[i].offset, self.marks[i].kind);
+            position = position.advance(self.input.slice(prev, offset));
+            prev = offset;
+            let key = match kind {
+                MarkKind::Key | MarkKind::FirstKey => self.key_at(offset),
+                MarkKind::Collection | MarkKind::Value => None,
+            };
+            let c = self.input.char_at(offset);
+            let mark = &mut self.marks[i];
+            (mark.position, mark.char, mark.key) = (Some(position), c, key);
+        }
+        self.input.discard(keep);
+    }
+
+    // The position of the given offset, unless we've discarded the input there without marking
+    // it.
+    fn position(&self, offset: usize) -> Option<Position> {
+        if offset >= self.input.start() {
+            Some(self.input.position(offset))
+        } else {
+            self.mark_at(offset).and_then(|mark| mark.position)
+        }
+    }
+
+    // Fails with the given code if we're parsing strict JSON.
+    fn reject_in_json(&mut self, offset: usize, code: ErrorCode) -> Result<()> {
+        match self.options.dialect {
+            Dialect::Json5 => Ok(()),
+            Dialect::Json => {
+                let err = self.err_at(offset, code);
+                match &mut self.recovered {
+                    // The input is still valid JSON5, so there's nothing to recover from.
+                    Some(errors) => {
+                        errors.push(err);
+                        Ok(())
+                    }
+                    None => Err(err),
                 }
-                None => return Err(self.err_at(offset, ErrorCode::InvalidBytes)),
             }
         }
-        Ok(bytes)
     }
 
-    fn err_at(&self, offset: usize, code: ErrorCode) -> Error {
-        Error::new_at(Position::from_offset(offset, self.input), code)
+    // The span of the char at the given offset (or an empty span at EOF).
+    fn char_span(&self, offset: usize) -> Range<usize> {
+        let c = if offset >= self.input.start() {
+            self.input.char_at(offset)
+        } else {
+            self.mark_at(offset).and_then(|mark| mark.char)
+        };
+        offset..offset + c.map_or(0, char::len_utf8)
+    }
+
+    pub(crate) fn err_at(&self, offset: usize, code: ErrorCode) -> Error {
+        self.err_in(self.char_span(offset), code)
+    }
+
+    fn err_in(&self, span: Range<usize>, code: ErrorCode) -> Error {
+        self.positioned(Error::new(code), span)
+    }
+
+    fn custom_err_in<T: Display>(&self, span: Range<usize>, msg: T) -> Error {
+        self.positioned(Error::custom(msg), span)
+    }
+
+    // Positions the error at the start of the span, if we know where that is.
+    fn positioned(&self, err: Error, span: Range<usize>) -> Error {
+        match self.position(span.start) {
+            Some(position) => err.with_position(position).with_span(span),
+            None => err,
+        }
+    }
+
+    // Adds the key at the given offset to the error's path. We only look at the key again when
+    // something goes wrong (or we're about to discard it), so that the happy path doesn't pay for
+    // it.
+    fn with_key(&mut self, err: Error, key_offset: usize) -> Error {
+        let key = if key_offset >= self.input.start() {
+            self.key_at(key_offset)
+        } else {
+            self.mark_at(key_offset).and_then(|mark| mark.key.clone())
+        };
+        match key {
+            Some(key) => err.with_key(&key),
+            None => err,
+        }
+    }
+
+    fn key_at(&mut self, key_offset: usize) -> Option<String> {
+        let offset = self.offset;
+        self.offset = key_offset;
+        let key = self.parse_key();
+        self.offset = offset;
+        key.ok().map(|(_, key)| key.to_owned())
+    }
+
+    // Errors from parsing the text of a key as another type (e.g. a number) are positioned within
+    // the key, so we move them to the key itself.
+    fn key_err(&self, err: Error, offset: usize) -> Error {
+        self.with_position(err.without_position(), offset)
+    }
+
+    // Errors from visitors don't know where they occured, so we position them at the start of the
+    // value being visited, and span them up to wherever we've parsed to.
+    pub(crate) fn with_position(&self, err: Error, offset: usize) -> Error {
+        if err.position().is_some() {
+            return err;
+        }
+        self.positioned(err, offset..self.offset)
+    }
+}
+
+// Recovering versions of the parsing methods above, used by `validate`. Errors are recorded with
+// `recover`, and only errors we can't carry on from (exceeding the recursion limit) are returned.
+impl Deserializer<'_> {
+    fn recover(&mut self, err: Error) {
+        let err = self.with_position(err, self.offset);
+        if let Some(errors) = &mut self.recovered
+            // An error right where the last one was is just a knock-on effect of it.
+            && errors.last().is_none_or(|last| last.offset() != err.offset())
+        {
+            errors.push(err);
+        }
+    }
+
+    fn recover_whitespace(&mut self) {
+        if let Err(err) = self.skip_whitespace() {
+            self.recover(err);
+        }
     }
 
-    fn custom_err_at<T: Display>(&self, offset: usize, msg: T) -> Error {
-        Error::custom_at(Position::from_offset(offset, self.input), msg)
+    // Skips ahead to somewhere we can sensibly carry on parsing from.
+    fn resync(&mut self) {
+        while let Some((_, c)) = self.peek() {
+            if matches!(c, ',' | ':' | ']' | '}') {
+                break;
+            }
+            self.next();
+        }
+    }
+
+    // Skips to the end of a string we've given up on (or the end of the line, since strings can't
+    // contain line terminators), so that punctuation inside it isn't mistaken for structure.
+    fn resync_string(&mut self, delimiter: char) {
+        while let Some((_, c)) = self.peek() {
+            if crate::char::is_json5_line_terminator(c) {
+                return;
+            }
+            self.next();
+            if c == '\\' {
+                self.next();
+            } else if c == delimiter {
+                return;
+            }
+        }
+    }
+
+    // `closers` holds the closing bracket of each collection we're inside.
+    fn recover_value(&mut self, closers: &mut Vec<char>) -> Result<()> {
+        self.recover_whitespace();
+        match self.peek() {
+            Some((offset, c @ ('[' | '{'))) => {
+                self.next();
+                self.enter_collection(offset)?;
+                self.recover_collection(offset, if c == '[' { ']' } else { '}' }, closers)?;
+                self.leave_collection();
+            }
+            Some((start, c)) => {
+                if let Err(err) = IgnoredAny::deserialize(&mut *self) {
+                    let offset = err.offset();
+                    self.recover(err);
+                    match c {
+                        '"' | '\'' => {
+                            self.offset = start;
+                            self.next();
+                            self.resync_string(c);
+                        }
+                        _ => {
+                            // Back up to the offending character, in case it's where we should
+                            // carry on from (e.g. the comma in `[tru, 1]`).
+                            if let Some(offset) = offset.filter(|&o| start <= o && o < self.offset)
+                            {
+                                self.offset = offset;
+                            }
+                        }
+                    }
+                    self.resync();
+                }
+            }
+            // If we're in a collection, it'll report the EOF itself.
+            None if closers.is_empty() => self.recover(self.eof(ErrorCode::EofParsingValue)),
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn recover_collection(
+        &mut self,
+        open: usize,
+        close: char,
+        closers: &mut Vec<char>,
+    ) -> Result<()> {
+        let (eof, expected_close, expected_element) = if close == ']' {
+            (
+                ErrorCode::EofParsingArray,
+                ErrorCode::ExpectedClosingBracket,
+                ErrorCode::ExpectedValue,
+            )
+        } else {
+            (
+                ErrorCode::EofParsingObject,
+                ErrorCode::ExpectedClosingBrace,
+                ErrorCode::ExpectedIdentifier,
+            )
+        };
+        let mut first_offsets = HashMap::new();
+        // Whether the last thing we parsed was an element, and so should be followed by a comma.
+        let mut need_comma = false;
+        let mut trailing_comma = None;
+
+        closers.push(close);
+        loop {
+            self.discard();
+            self.recover_whitespace();
+            let Some((offset, c)) = self.peek() else {
+                let err = match self.input.error() {
+                    Some(err) => err.clone(),
+                    None => self.err_at(open, eof),
+                };
+                self.recover(err);
+                break;
+            };
+            match c {
+                _ if c == close => {
+                    self.next();
+                    if let Some(comma) = trailing_comma {
+                        self.reject_in_json(comma, ErrorCode::TrailingCommaInJson)?;
+                    }
+                    break;
+                }
+                ']' | '}' => {
+                    self.recover(self.err_at(offset, expected_close));
+                    // If this closes a collection we're inside, assume we're missing our own
+                    // closing bracket. Otherwise it's a stray we can skip.
+                    if closers.contains(&c) {
+                        break;
+                    }
+                    self.next();
+                }
+                ',' => {
+                    self.next();
+                    if need_comma {
+                        need_comma = false;
+                        trailing_comma = Some(offset);
+                    } else {
+                        self.recover(self.err_at(offset, expected_element));
+                    }
+                }
+                ':' if close == ']' => {
+                    self.next();
+                    let code = if need_comma {
+                        ErrorCode::ExpectedComma
+                    } else {
+                        ErrorCode::ExpectedValue
+                    };
+                    self.recover(self.err_at(offset, code));
+                    need_comma = false;
+                }
+                _ => {
+                    if need_comma {
+                        self.recover(self.err_at(offset, ErrorCode::ExpectedComma));
+                    }
+                    if close == ']' {
+                        self.recover_value(closers)?;
+                    } else {
+                        self.recover_entry(closers, &mut first_offsets)?;
+                    }
+                    need_comma = true;
+                    trailing_comma = None;
+                }
+            }
+        }
+        closers.pop();
+        Ok(())
     }
 
-    fn with_position(&self, err: Error, offset: usize) -> Error {
-        err.with_position(Position::from_offset(offset, self.input))
+    fn recover_entry(
+        &mut self,
+        closers: &mut Vec<char>,
+        first_offsets: &mut HashMap<String, usize>,
+    ) -> Result<()> {
+        let valid_key = match self.parse_key() {
+            Ok((offset, key)) => {
+                if self.options.duplicate_keys == DuplicateKeys::Error {
+                    match first_offsets.entry(key.to_owned()) {
+                        Entry::Occupied(entry) => {
+                            let first = self.position(*entry.get()).expect("first keys are marked");
+                            let err =
+                                self.err_in(offset..self.offset, ErrorCode::DuplicateKey { first });
+                            // Not via `recover`, since this can coincide with a dialect error.
+                            if let Some(errors) = &mut self.recovered {
+                                errors.push(err);
+                            }
+                        }
+                        Entry::Vacant(entry) => {
+                            entry.insert(offset);
+                            self.mark(offset, MarkKind::FirstKey);
+                        }
+                    }
+                }
+                true
+            }
+            Err(err) => {
+                self.recover(err);
+                self.resync();
+                false
+            }
+        };
+
+        self.recover_whitespace();
+        match self.peek() {
+            Some((_, ':')) => {
+                self.next();
+            }
+            Some((offset, c)) => {
+                if valid_key {
+                    self.recover(self.err_at(offset, ErrorCode::ExpectedColon));
+                }
+                // Assume the colon is missing, unless there's no value either.
+                if matches!(c, ',' | ']' | '}') {
+                    return Ok(());
+                }
+            }
+            None => return Ok(()),
+        }
+        self.recover_value(closers)
     }
 }
 
@@ -581,9 +1505,20 @@ macro_rules! deserialize_string {
 macro_rules! deserialize_bytes {
     ($method:ident) => {
         fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-            let (offset, s) = self.parse_string()?;
+            self.skip_whitespace()?;
+            let array = if self.options.detect_bytes {
+                self.peek().is_some_and(|(_, c)| c == '[')
+            } else {
+                self.options.bytes == ByteEncoding::Array
+            };
+            let (offset, bytes) = if array {
+                self.parse_byte_array()?
+            } else {
+                let (offset, s) = self.parse_string()?;
+                (offset, self.decode_bytes(offset, &s)?)
+            };
             visitor
-                .visit_byte_buf(self.decode_hex(offset, &s)?)
+                .visit_byte_buf(bytes)
                 .map_err(|err| self.with_position(err, offset))
         }
     };
@@ -601,15 +1536,14 @@ macro_rules! deserialize_collection {
         $expected_closing:expr,
     ) => {
         fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-            self.skip_whitespace()?;
-            let offset = self.expect_char($open, $eof, $expected_opening)?;
+            let offset = self.parse_token(|de| de.expect_char($open, $eof, $expected_opening))?;
+            self.enter_collection(offset)?;
             let value = visitor
-                .$visit($access {
-                    de: self,
-                    first: true,
-                })
+                .$visit($access::new(self))
                 .map_err(|err| self.with_position(err, offset))?;
-            self.expect_collection_end($close, $eof, $expected_closing)?;
+            self.expect_collection_end($close, $eof, $expected_closing)
+                .map_err(|err| self.with_position(err, offset))?;
+            self.leave_collection();
             Ok(value)
         }
     };
@@ -668,15 +1602,18 @@ impl<'de> serde::de::Deserializer<'de> for &mut Deserializer<'de> {
     }
 
     fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.skip_whitespace()?;
-        let (offset, c) = self.peek_or(ErrorCode::EofParsingValue)?;
-        if c == 'n' {
+        let (offset, c) = self.parse_token(|de| de.peek_or(ErrorCode::EofParsingValue))?;
+        let marks = self.marks.len();
+        self.mark(offset, MarkKind::Value);
+        let value = if c == 'n' {
             self.parse_null()?;
             visitor.visit_none()
         } else {
             visitor.visit_some(&mut *self)
         }
-        .map_err(|err| self.with_position(err, offset))
+        .map_err(|err| self.with_position(err, offset))?;
+        self.marks.truncate(marks);
+        Ok(value)
     }
 
     fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
@@ -730,18 +1667,23 @@ impl<'de> serde::de::Deserializer<'de> for &mut Deserializer<'de> {
         _: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
-        self.skip_whitespace()?;
-        match self.peek_or(ErrorCode::EofParsingValue)? {
+        match self.parse_token(|de| de.peek_or(ErrorCode::EofParsingValue))? {
             (offset, '{') => {
                 self.next();
+                self.enter_collection(offset)?;
                 let value = visitor
-                    .visit_enum(VariantAccess { de: self })
+                    .visit_enum(VariantAccess {
+                        de: self,
+                        key_offset: offset,
+                    })
                     .map_err(|err| self.with_position(err, offset))?;
                 self.expect_collection_end(
                     '}',
                     ErrorCode::EofParsingObject,
                     ErrorCode::ExpectedClosingBrace,
-                )?;
+                )
+                .map_err(|err| self.with_position(err, offset))?;
+                self.leave_collection();
                 Ok(value)
             }
             (offset, '"' | '\'') => visitor
@@ -755,8 +1697,7 @@ impl<'de> serde::de::Deserializer<'de> for &mut Deserializer<'de> {
     }
 
     fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.skip_whitespace()?;
-        match self.peek_or(ErrorCode::EofParsingValue)? {
+        match self.parse_token(|de| de.peek_or(ErrorCode::EofParsingValue))? {
             (_, 'n') => self.deserialize_unit(visitor),
             (_, 't' | 'f') => self.deserialize_bool(visitor),
             (_, '"' | '\'') => self.deserialize_str(visitor),
@@ -775,6 +1716,17 @@ impl<'de> serde::de::Deserializer<'de> for &mut Deserializer<'de> {
 struct SeqAccess<'a, 'de: 'a> {
     de: &'a mut Deserializer<'de>,
     first: bool,
+    index: usize,
+}
+
+impl<'a, 'de> SeqAccess<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>) -> Self {
+        Self {
+            de,
+            first: true,
+            index: 0,
+        }
+    }
 }
 
 impl<'de> serde::de::SeqAccess<'de> for SeqAccess<'_, 'de> {
@@ -784,55 +1736,168 @@ impl<'de> serde::de::SeqAccess<'de> for SeqAccess<'_, 'de> {
     where
         T: serde::de::DeserializeSeed<'de>,
     {
+        self.de.discard();
         self.de.skip_whitespace()?;
         if self.de.peek().is_some_and(|(_, c)| c == ']') {
             return Ok(None);
         }
 
         if !self.first {
-            self.de
-                .expect_char(',', ErrorCode::EofParsingArray, ErrorCode::ExpectedComma)?;
+            let offset =
+                self.de
+                    .expect_char(',', ErrorCode::EofParsingArray, ErrorCode::ExpectedComma)?;
 
             self.de.skip_whitespace()?;
             if self.de.peek().is_some_and(|(_, c)| c == ']') {
+                self.de
+                    .reject_in_json(offset, ErrorCode::TrailingCommaInJson)?;
                 return Ok(None);
             }
         }
         self.first = false;
 
-        seed.deserialize(&mut *self.de).map(Some)
+        let index = self.index;
+        self.index += 1;
+        seed.deserialize(&mut *self.de)
+            .map(Some)
+            .map_err(|err| err.with_index(index))
     }
 }
 
 struct MapAccess<'a, 'de: 'a> {
     de: &'a mut Deserializer<'de>,
     first: bool,
+    // The offset of the first occurrence of each key we've seen so far. Only tracked for
+    // DuplicateKeys::Error and DuplicateKeys::FirstWins.
+    first_offsets: HashMap<String, usize>,
+    // The offset of the last occurrence of each key in the whole object. Only tracked for
+    // DuplicateKeys::LastWins.
+    last_offsets: HashMap<String, usize>,
+    // The offset of the key of the current entry.
+    key_offset: usize,
 }
 
-impl<'de> serde::de::MapAccess<'de> for MapAccess<'_, 'de> {
-    type Error = Error;
+impl<'a, 'de> MapAccess<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>) -> Self {
+        let mut access = Self {
+            de,
+            first: true,
+            first_offsets: HashMap::new(),
+            last_offsets: HashMap::new(),
+            key_offset: 0,
+        };
+        if access.de.options.duplicate_keys == DuplicateKeys::LastWins {
+            let start = access.de.offset;
+            access.last_offsets = if let Some(last_offsets) = access.de.last_offsets.remove(&start)
+            {
+                last_offsets
+            } else {
+                let (depth, hold, marks) = (access.de.depth, access.de.hold, access.de.marks.len());
+                // We come back to the start of the object after scanning it, so we can't discard
+                // it while reading.
+                access.de.hold = Some(hold.map_or(start, |hold| hold.min(start)));
+                // If the object is malformed we'll find out when we parse it for real.
+                let last_offsets = access.de.scan_object().unwrap_or_default();
+                (access.de.offset, access.de.depth, access.de.hold) = (start, depth, hold);
+                access.de.marks.truncate(marks);
+                last_offsets
+            };
+        }
+        access
+    }
 
-    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
-    where
-        K: serde::de::DeserializeSeed<'de>,
-    {
+    // Skips whitespace and commas between entries. Returns false if we've reached the end of the
+    // object.
+    fn next_entry(&mut self) -> Result<bool> {
+        self.de.discard();
         self.de.skip_whitespace()?;
         if self.de.peek().is_some_and(|(_, c)| c == '}') {
-            return Ok(None);
+            return Ok(false);
         }
 
         if !self.first {
-            self.de
-                .expect_char(',', ErrorCode::EofParsingObject, ErrorCode::ExpectedComma)?;
+            let offset =
+                self.de
+                    .expect_char(',', ErrorCode::EofParsingObject, ErrorCode::ExpectedComma)?;
 
             self.de.skip_whitespace()?;
             if self.de.peek().is_some_and(|(_, c)| c == '}') {
-                return Ok(None);
+                self.de
+                    .reject_in_json(offset, ErrorCode::TrailingCommaInJson)?;
+                return Ok(false);
             }
         }
         self.first = false;
 
-        seed.deserialize(MapKey { de: self.de }).map(Some)
+        Ok(true)
+    }
+
+    fn skip_value(&mut self) -> Result<()> {
+        self.de.skip_whitespace()?;
+        self.de
+            .expect_char(':', ErrorCode::EofParsingObject, ErrorCode::ExpectedColon)?;
+        IgnoredAny::deserialize(&mut *self.de)?;
+        Ok(())
+    }
+
+    // Peeks at the next key to decide whether to keep the entry. If not, skips the entry
+    // entirely.
+    fn keep_entry(&mut self) -> Result<bool> {
+        let start = self.de.offset;
+        let (offset, key) = self.de.parse_key()?;
+        let keep = match self.de.options.duplicate_keys {
+            DuplicateKeys::Allow => true,
+            DuplicateKeys::Error => match self.first_offsets.entry(key.to_owned()) {
+                Entry::Occupied(entry) => {
+                    let first = self
+                        .de
+                        .position(*entry.get())
+                        .expect("first keys are marked");
+                    return Err(self
+                        .de
+                        .err_in(offset..self.de.offset, ErrorCode::DuplicateKey { first }));
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(offset);
+                    self.de.mark(offset, MarkKind::FirstKey);
+                    true
+                }
+            },
+            DuplicateKeys::FirstWins => match self.first_offsets.entry(key.to_owned()) {
+                Entry::Occupied(_) => false,
+                Entry::Vacant(entry) => {
+                    entry.insert(offset);
+                    true
+                }
+            },
+            DuplicateKeys::LastWins => self.last_offsets.get(&*key).is_none_or(|&o| o == offset),
+        };
+        if keep {
+            self.de.offset = start;
+        } else {
+            self.skip_value()?;
+        }
+        Ok(keep)
+    }
+}
+
+impl<'de> serde::de::MapAccess<'de> for MapAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: serde::de::DeserializeSeed<'de>,
+    {
+        loop {
+            if !self.next_entry()? {
+                return Ok(None);
+            }
+            if self.de.options.duplicate_keys == DuplicateKeys::Allow || self.keep_entry()? {
+                self.key_offset = self.de.offset;
+                self.de.mark(self.key_offset, MarkKind::Key);
+                return seed.deserialize(MapKey { de: self.de }).map(Some);
+            }
+        }
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
@@ -842,7 +1907,10 @@ impl<'de> serde::de::MapAccess<'de> for MapAccess<'_, 'de> {
         self.de.skip_whitespace()?;
         self.de
             .expect_char(':', ErrorCode::EofParsingObject, ErrorCode::ExpectedColon)?;
-        seed.deserialize(&mut *self.de)
+        match seed.deserialize(&mut *self.de) {
+            Ok(value) => Ok(value),
+            Err(err) => Err(self.de.with_key(err, self.key_offset)),
+        }
     }
 }
 
@@ -854,8 +1922,9 @@ macro_rules! deserialize_key_from_str {
     ($method:ident, $visit:ident) => {
         fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
             let (offset, key) = self.de.parse_key()?;
+            let value = from_str(&key).map_err(|err| self.de.key_err(err, offset))?;
             visitor
-                .$visit(from_str(&key)?)
+                .$visit(value)
                 .map_err(|err| self.de.with_position(err, offset))
         }
     };
@@ -879,7 +1948,7 @@ macro_rules! deserialize_bytes_key {
         fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
             let (offset, s) = self.de.parse_key()?;
             visitor
-                .visit_byte_buf(self.de.decode_hex(offset, &s)?)
+                .visit_byte_buf(self.de.decode_bytes(offset, &s)?)
                 .map_err(|err| self.de.with_position(err, offset))
         }
     };
@@ -914,7 +1983,7 @@ impl<'de> serde::de::Deserializer<'de> for MapKey<'_, 'de> {
 
     fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         let (offset, key) = self.de.parse_key()?;
-        from_str::<()>(&key)?;
+        from_str::<()>(&key).map_err(|err| self.de.key_err(err, offset))?;
         visitor
             .visit_unit()
             .map_err(|err| self.de.with_position(err, offset))
@@ -962,6 +2031,13 @@ impl<'de> serde::de::Deserializer<'de> for MapKey<'_, 'de> {
 
 struct VariantAccess<'a, 'de: 'a> {
     de: &'a mut Deserializer<'de>,
+    key_offset: usize,
+}
+
+impl VariantAccess<'_, '_> {
+    fn with_key<T>(self, res: Result<T>) -> Result<T> {
+        res.map_err(|err| self.de.with_key(err, self.key_offset))
+    }
 }
 
 impl<'de> serde::de::EnumAccess<'de> for VariantAccess<'_, 'de> {
@@ -969,10 +2045,13 @@ impl<'de> serde::de::EnumAccess<'de> for VariantAccess<'_, 'de> {
 
     type Variant = Self;
 
-    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self::Variant)>
     where
         V: serde::de::DeserializeSeed<'de>,
     {
+        self.de.skip_whitespace()?;
+        self.key_offset = self.de.offset;
+        self.de.mark(self.key_offset, MarkKind::Key);
         let variant = seed.deserialize(MapKey { de: &mut *self.de })?;
         self.de.skip_whitespace()?;
         self.de
@@ -985,15 +2064,18 @@ impl<'de> serde::de::VariantAccess<'de> for VariantAccess<'_, 'de> {
     type Error = Error;
 
     fn unit_variant(self) -> Result<()> {
-        serde::de::Deserialize::deserialize(self.de)
+        let res = serde::de::Deserialize::deserialize(&mut *self.de);
+        self.with_key(res)
     }
 
     fn newtype_variant_seed<T: serde::de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
-        seed.deserialize(self.de)
+        let res = seed.deserialize(&mut *self.de);
+        self.with_key(res)
     }
 
     fn tuple_variant<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value> {
-        serde::de::Deserializer::deserialize_seq(self.de, visitor)
+        let res = serde::de::Deserializer::deserialize_seq(&mut *self.de, visitor);
+        self.with_key(res)
     }
 
     fn struct_variant<V: Visitor<'de>>(
@@ -1001,7 +2083,8 @@ impl<'de> serde::de::VariantAccess<'de> for VariantAccess<'_, 'de> {
         fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
-        serde::de::Deserializer::deserialize_struct(self.de, "", fields, visitor)
+        let res = serde::de::Deserializer::deserialize_struct(&mut *self.de, "", fields, visitor);
+        self.with_key(res)
     }
 }
 
@@ -1065,7 +2148,7 @@ impl<'de> serde::de::VariantAccess<'de> for UnitVariantAccess<'_, 'de> {
     }
 }
 
-enum StringResult<'de> {
+pub(crate) enum StringResult<'de> {
     Borrowed(&'de str),
     Owned(String),
 }
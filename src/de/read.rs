@@ -0,0 +1,198 @@
+use std::{borrow::Cow, io, ops::Range};
+
+use crate::error::{Error, ErrorCode, Position};
+
+// How many bytes to ask a reader for at a time. It's also the least we discard at once, so that
+// we're not forever shuffling a few bytes along the buffer.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// The text a [`Deserializer`](super::Deserializer) parses. Either a string we have all of, or
+/// text decoded from an [`io::Read`] a chunk at a time, of which we only keep what we might still
+/// need to look at. Offsets are always into the whole input.
+pub(super) struct Input<'de> {
+    // The part of the input we have, which is all of it unless we're reading.
+    text: Cow<'de, str>,
+    // The offset and position of the start of `text`.
+    start: usize,
+    position: Position,
+    reader: Option<Reader<'de>>,
+}
+
+struct Reader<'de> {
+    inner: Box<dyn io::Read + 'de>,
+    // What we read in to. The first `pending` bytes were read last time but not decoded, because
+    // they're the start of a char we didn't have all of.
+    buf: Box<[u8]>,
+    pending: usize,
+    // Whether we've reached the end of the reader, or given up on it.
+    done: bool,
+    // Why we gave up, if we did. It's reported in place of the EOF it looks like.
+    error: Option<Error>,
+}
+
+impl<'de> Input<'de> {
+    pub fn from_str(input: &'de str) -> Self {
+        Self {
+            text: Cow::Borrowed(input),
+            start: 0,
+            position: Position { line: 0, column: 0 },
+            reader: None,
+        }
+    }
+
+    pub fn from_reader<R: io::Read + 'de>(reader: R) -> Self {
+        Self {
+            text: Cow::Owned(String::new()),
+            start: 0,
+            position: Position { line: 0, column: 0 },
+            reader: Some(Reader {
+                inner: Box::new(reader),
+                buf: vec![0; CHUNK_SIZE].into_boxed_slice(),
+                pending: 0,
+                done: false,
+                error: None,
+            }),
+        }
+    }
+
+    pub fn is_reading(&self) -> bool {
+        self.reader.is_some()
+    }
+
+    // The offset of the first char we still have.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    // The whole input, if it's a string we can borrow from.
+    pub fn borrowed(&self) -> Option<&'de str> {
+        match self.text {
+            Cow::Borrowed(text) => Some(text),
+            Cow::Owned(_) => None,
+        }
+    }
+
+    pub fn error(&self) -> Option<&Error> {
+        self.reader
+            .as_ref()
+            .and_then(|reader| reader.error.as_ref())
+    }
+
+    // The char at the given offset, if we have it.
+    pub fn char_at(&self, offset: usize) -> Option<char> {
+        self.text
+            .get(offset.checked_sub(self.start)?..)?
+            .chars()
+            .next()
+    }
+
+    // The char at the given offset, reading more of the input if we need to. `None` at the end of
+    // the input, or if reading failed.
+    pub fn read_char_at(&mut self, offset: usize) -> Option<char> {
+        loop {
+            if let Some(c) = self.text[offset - self.start..].chars().next() {
+                return Some(c);
+            }
+            if !self.fill() {
+                return None;
+            }
+        }
+    }
+
+    // The char before the given offset, if we still have it.
+    pub fn char_before(&self, offset: usize) -> Option<char> {
+        self.text[..offset.checked_sub(self.start)?]
+            .chars()
+            .next_back()
+    }
+
+    // The text in the given range, if we still have it all.
+    pub fn get(&self, range: Range<usize>) -> Option<&str> {
+        self.text
+            .get(range.start.checked_sub(self.start)?..range.end.checked_sub(self.start)?)
+    }
+
+    // The text from `start` to `end`, which we must still have.
+    pub fn slice(&self, start: usize, end: usize) -> &str {
+        &self.text[start - self.start..end - self.start]
+    }
+
+    // The position of the given offset, which we must still have.
+    pub fn position(&self, offset: usize) -> Position {
+        self.position.advance(&self.text[..offset - self.start])
+    }
+
+    // How much of the input before `keep` we can discard, if it's enough to be worth it. The
+    // result is the new start.
+    pub fn discardable(&self, keep: usize) -> Option<usize> {
+        if !self.is_reading() {
+            return None;
+        }
+        let mut len = keep - self.start;
+        // Positions count a CR LF as one line terminator, so we keep them together.
+        if self.text[..len].ends_with('\r') {
+            len -= 1;
+        }
+        // Discarding moves everything after `keep` along, so we wait until that's no more than
+        // what we're discarding.
+        (len >= CHUNK_SIZE && len >= self.text.len() - len).then_some(self.start + len)
+    }
+
+    // Discards the input before `keep`, which `discardable` returned.
+    pub fn discard(&mut self, keep: usize) {
+        let len = keep - self.start;
+        self.position = self.position.advance(&self.text[..len]);
+        self.text.to_mut().drain(..len);
+        self.start = keep;
+    }
+
+    // Reads and decodes another chunk of the input. Returns false if there's no more to read.
+    fn fill(&mut self) -> bool {
+        let end = self.start + self.text.len();
+        let Some(reader) = self.reader.as_mut().filter(|reader| !reader.done) else {
+            return false;
+        };
+
+        let res = loop {
+            match reader.inner.read(&mut reader.buf[reader.pending..]) {
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                res => break res,
+            }
+        };
+        let n = *res.as_ref().unwrap_or(&0);
+        let len = reader.pending + n;
+
+        let (valid, invalid) = match std::str::from_utf8(&reader.buf[..len]) {
+            Ok(_) => (len, None),
+            // An incomplete char at the end of what we've read so far is finished by the next
+            // read, unless there isn't one.
+            Err(err) if err.error_len().is_none() && n > 0 => (err.valid_up_to(), None),
+            Err(err) => (
+                err.valid_up_to(),
+                Some(err.error_len().unwrap_or(len - err.valid_up_to())),
+            ),
+        };
+        let text = std::str::from_utf8(&reader.buf[..valid]).expect("valid up to here");
+        self.text.to_mut().push_str(text);
+        reader.buf.copy_within(valid..len, 0);
+        reader.pending = len - valid;
+
+        let offset = end + valid;
+        let error = match (res, invalid) {
+            (Err(err), _) => Some(Error::from(err).with_span(offset..offset)),
+            (Ok(_), Some(len)) => {
+                Some(Error::new(ErrorCode::InvalidUtf8).with_span(offset..offset + len))
+            }
+            (Ok(0), None) => {
+                reader.done = true;
+                return valid > 0;
+            }
+            (Ok(_), None) => None,
+        };
+        if let Some(error) = error {
+            reader.done = true;
+            reader.error = Some(error.with_position(self.position.advance(&self.text)));
+        }
+        true
+    }
+}
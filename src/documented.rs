@@ -0,0 +1,132 @@
+use serde::{Serialize, Serializer};
+
+use crate::{error::Result, to_string};
+
+/// A type that can be serialized with its documentation, writing the doc comment of each field as
+/// a comment before it, as with [`Commented`](crate::Commented).
+///
+/// Rather than implementing this by hand, enable the `derive` feature and use
+/// `#[derive(json5::Documented)]` alongside `#[derive(Serialize)]`. The derive reads the `///`
+/// comments on each field of a struct with named fields. Fields whose type is also `Documented`
+/// are written with their comments too.
+///
+/// So that the output matches what [`serde::Serialize`] would write, the derive follows these
+/// serde attributes:
+///
+/// - on the struct: `rename`, `rename_all`, `default` and `bound`;
+/// - on fields: `rename`, `skip`, `skip_serializing`, `skip_deserializing`, `skip_serializing_if`
+///   and `default`.
+///
+/// Attributes that only affect deserializing (`deny_unknown_fields`, `from`, `try_from`,
+/// `expecting` and `crate` on the struct, and `alias`, `deserialize_with` and `borrow` on fields)
+/// are ignored. Any other serde attribute, e.g. `flatten` or `serialize_with`, is a compile error.
+///
+/// [`default_config`] uses this to generate a commented config file from a struct's defaults.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "derive")] {
+/// use serde_derive::Serialize;
+///
+/// #[derive(Serialize, json5::Documented)]
+/// #[serde(rename_all = "kebab-case")]
+/// struct Config {
+///     /// Port the HTTP server listens on.
+///     #[serde(default = "default_port")]
+///     port: u16,
+///     /// Hosts to accept requests for.
+///     allowed_hosts: Vec<String>,
+///     #[serde(skip)]
+///     cache: Vec<u8>,
+/// }
+///
+/// impl Default for Config {
+///     fn default() -> Self {
+///         Self {
+///             port: 0,
+///             allowed_hosts: vec!["localhost".to_owned()],
+///             cache: Vec::new(),
+///         }
+///     }
+/// }
+///
+/// fn default_port() -> u16 {
+///     8080
+/// }
+///
+/// assert_eq!(json5::default_config::<Config>()?, "{
+///   // Port the HTTP server listens on.
+///   port: 8080,
+///   // Hosts to accept requests for.
+///   \"allowed-hosts\": [
+///     \"localhost\",
+///   ],
+/// }");
+/// # }
+/// # Ok::<(), json5::Error>(())
+/// ```
+pub trait Documented {
+    /// Serialize `self` as [`serde::Serialize`] would, with a comment before each documented
+    /// field.
+    ///
+    /// # Errors
+    /// As for [`serde::Serialize::serialize`].
+    fn serialize_documented<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error>;
+
+    /// The value that deserializing `{}` would give: fields with a serde `default` take it, and
+    /// the rest come from [`Default`].
+    #[must_use]
+    fn documented_default() -> Self
+    where
+        Self: Sized;
+}
+
+/// Serializes the wrapped value with [`Documented::serialize_documented`], so that it can be
+/// passed to [`to_string_with`](crate::to_string_with) and friends.
+#[derive(Debug, Clone, Copy)]
+pub struct WithDocs<'a, T: ?Sized>(pub &'a T);
+
+impl<T: ?Sized + Documented> Serialize for WithDocs<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.0.serialize_documented(serializer)
+    }
+}
+
+/// Serialize the default value of `T` as a JSON5 string, with the doc comment of each field
+/// written before it. Useful for a `--print-default-config` flag, or for writing a config file on
+/// first run. See [`Documented`].
+///
+/// # Errors
+/// Fails if `T`'s implementation of `Serialize` decides to fail, or if `T` contains a map with
+/// non-string keys.
+pub fn default_config<T: Documented>() -> Result<String> {
+    to_string(&WithDocs(&T::documented_default()))
+}
+
+// Picks `WithDocs` for fields whose type is `Documented`, and a plain reference otherwise, by
+// "autoref specialization": `(&Probe(&field)).wrap()` finds `ViaDocumented` before it has to
+// autoref again to find `ViaSerialize`. Used by `#[derive(Documented)]`. Not public API.
+pub struct Probe<'a, T: ?Sized>(pub &'a T);
+
+pub trait ViaDocumented<'a, T: ?Sized> {
+    fn wrap(&self) -> WithDocs<'a, T>;
+}
+
+impl<'a, T: ?Sized + Documented> ViaDocumented<'a, T> for Probe<'a, T> {
+    fn wrap(&self) -> WithDocs<'a, T> {
+        WithDocs(self.0)
+    }
+}
+
+pub trait ViaSerialize<'a, T: ?Sized> {
+    fn wrap(&self) -> &'a T;
+}
+
+impl<'a, T: ?Sized + Serialize> ViaSerialize<'a, T> for &Probe<'a, T> {
+    fn wrap(&self) -> &'a T {
+        self.0
+    }
+}
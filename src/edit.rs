@@ -0,0 +1,662 @@
+//! Editing JSON5 documents without disturbing their formatting.
+//!
+//! A [`Document`] is a [lossless syntax tree](crate::cst) with methods for reading and modifying
+//! values by path. Anything that isn't touched by an edit (comments, whitespace, quote style, how
+//! numbers are written) is printed back exactly as it was written. New values are written in the
+//! same style as the [`Serializer`](crate::Serializer), indented to fit in where they're put, with
+//! the same indentation for each level of nesting as the rest of the document.
+//!
+//! Paths are object keys and array indices separated by dots, e.g. `servers.0.port`. A key that
+//! contains a `.` or `[` can be written as a quoted JSON5 string in brackets, and an index can be
+//! written in brackets too, with or without a dot before it, e.g. `hosts['example.com'].ports[0]`.
+//! The empty path refers to the whole document. If an object contains the same key more than once,
+//! paths refer to the last occurrence.
+//!
+//! ```
+//! use json5::edit::Document;
+//!
+//! let mut doc: Document = "{
+//!   // Bump me.
+//!   version: '1.0.0',
+//!   dependencies: { serde: '1' },
+//! }"
+//! .parse()?;
+//!
+//! doc.set("version", "1.1.0")?;
+//! doc.insert("dependencies.indexmap", "2")?;
+//! doc.insert("keywords", &["json5", "config"])?;
+//!
+//! assert_eq!(doc.to_string(), "{
+//!   // Bump me.
+//!   version: \"1.1.0\",
+//!   dependencies: { serde: '1', indexmap: \"2\" },
+//!   keywords: [
+//!     \"json5\",
+//!     \"config\",
+//!   ],
+//! }");
+//! # Ok::<(), json5::Error>(())
+//! ```
+
+use std::{
+    borrow::Cow,
+    fmt::{self, Display, Formatter},
+    mem,
+    str::FromStr,
+};
+
+use serde::Serialize;
+
+use crate::{
+    Indent, SerializerConfig,
+    cst::{self, Element, Member, Token, TokenKind, Trivia, TriviaKind},
+    error::{Error, ErrorCode, Result},
+};
+
+/// A JSON5 document that can be edited without disturbing its formatting. See the
+/// [module docs](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    cst: cst::Document,
+    // The line terminator to use for new lines, matching the rest of the document.
+    newline: &'static str,
+    // The indentation for each level of nesting in new values, matching the rest of the document.
+    indent: Indent,
+}
+
+impl Document {
+    /// The underlying syntax tree.
+    #[must_use]
+    pub fn as_cst(&self) -> &cst::Document {
+        &self.cst
+    }
+
+    /// The value at the given path, if there is one.
+    #[must_use]
+    pub fn get(&self, path: &str) -> Option<&cst::Value> {
+        segments(path)
+            .ok()?
+            .iter()
+            .try_fold(&self.cst.value, |value, segment| match value {
+                cst::Value::Scalar(_) => None,
+                cst::Value::Array(array) => array
+                    .elements
+                    .get(segment.parse::<usize>().ok()?)
+                    .map(|element| &element.value),
+                cst::Value::Object(object) => object
+                    .members
+                    .iter()
+                    .rfind(|member| member.key() == *segment)
+                    .map(|member| &member.value),
+            })
+    }
+
+    /// The value at the given path, if there is one, for editing the syntax tree directly.
+    pub fn get_mut(&mut self, path: &str) -> Option<&mut cst::Value> {
+        find_mut(&mut self.cst.value, &segments(path).ok()?, String::new()).map(|(value, _)| value)
+    }
+
+    /// Replace the value at the given path, keeping the comments and whitespace around it. If the
+    /// path refers to a key that's missing from an object, the key is inserted at the end of the
+    /// object.
+    ///
+    /// # Errors
+    /// Fails if there's no value at the path (other than a missing object key), or if the value
+    /// can't be serialized.
+    pub fn set<T: ?Sized + Serialize>(&mut self, path: &str, value: &T) -> Result<()> {
+        let segments = segments(path)?;
+        let (newline, unit) = (self.newline, self.indent);
+        if let Some((old, indent)) = find_mut(&mut self.cst.value, &segments, String::new()) {
+            replace(old, render(value, &indent, newline, unit)?);
+            return Ok(());
+        }
+        match self.parent_mut(&segments)? {
+            (cst::Value::Object(_), ..) => self.insert(path, value),
+            _ => Err(Error::new(ErrorCode::PathNotFound)),
+        }
+    }
+
+    /// Insert a value at the given path. If the path refers to an object key, the key is added at
+    /// the end of the object (or if the key is already present, its value is replaced, as with
+    /// [`set`](Document::set)). If the path refers to an array index, the value is inserted at
+    /// that index, shifting the elements after it along. The index can be the length of the array
+    /// to append.
+    ///
+    /// # Errors
+    /// Fails if the parent of the path doesn't exist or isn't an object or array, if an index is
+    /// out of bounds, or if the value can't be serialized.
+    pub fn insert<T: ?Sized + Serialize>(&mut self, path: &str, value: &T) -> Result<()> {
+        let segments = segments(path)?;
+        let (newline, unit) = (self.newline, self.indent);
+        let (parent, last, indent) = self.parent_mut(&segments)?;
+        let multiline = is_multiline(parent);
+        match parent {
+            cst::Value::Scalar(_) => Err(Error::new(ErrorCode::PathNotFound)),
+            cst::Value::Array(array) => {
+                let index = last
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&index| index <= array.elements.len())
+                    .ok_or_else(|| Error::new(ErrorCode::PathNotFound))?;
+                let entry_indent = entry_indent(&array.elements, index, &indent, multiline, unit);
+                let element = Element {
+                    value: render(value, &entry_indent, newline, unit)?,
+                    comma: None,
+                };
+                insert_entry(
+                    &mut array.open,
+                    &mut array.elements,
+                    &mut array.close,
+                    index,
+                    element,
+                    Layout {
+                        multiline,
+                        indent: &indent,
+                        entry_indent: &entry_indent,
+                        newline,
+                    },
+                );
+                Ok(())
+            }
+            cst::Value::Object(object) => {
+                if let Some(member) = object.members.iter_mut().rfind(|m| m.key() == last) {
+                    let indent = if multiline {
+                        indentation(&member.key)
+                    } else {
+                        &indent
+                    };
+                    replace(&mut member.value, render(value, indent, newline, unit)?);
+                    return Ok(());
+                }
+                let index = object.members.len();
+                let entry_indent = entry_indent(&object.members, index, &indent, multiline, unit);
+                let mut colon = Token::new(TokenKind::Colon, ":");
+                colon.trailing = whitespace(" ");
+                let member = Member {
+                    key: render_key(last)?,
+                    colon,
+                    value: render(value, &entry_indent, newline, unit)?,
+                    comma: None,
+                };
+                insert_entry(
+                    &mut object.open,
+                    &mut object.members,
+                    &mut object.close,
+                    index,
+                    member,
+                    Layout {
+                        multiline,
+                        indent: &indent,
+                        entry_indent: &entry_indent,
+                        newline,
+                    },
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Remove the value at the given path from its parent object or array, along with any
+    /// comments before it, and return it.
+    pub fn remove(&mut self, path: &str) -> Option<cst::Value> {
+        let segments = segments(path).ok()?;
+        match self.parent_mut(&segments).ok()? {
+            (cst::Value::Scalar(_), ..) => None,
+            (cst::Value::Array(array), last, _) => {
+                let index = last
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&index| index < array.elements.len())?;
+                Some(remove_entry(&mut array.elements, index).value)
+            }
+            (cst::Value::Object(object), last, _) => {
+                let index = object.members.iter().rposition(|m| m.key() == last)?;
+                Some(remove_entry(&mut object.members, index).value)
+            }
+        }
+    }
+
+    /// Rename the object key at the given path, keeping its value and position.
+    ///
+    /// # Errors
+    /// Fails if the path doesn't refer to an object key.
+    pub fn rename_key(&mut self, path: &str, new_key: &str) -> Result<()> {
+        let segments = segments(path)?;
+        let (cst::Value::Object(object), last, _) = self.parent_mut(&segments)? else {
+            return Err(Error::new(ErrorCode::PathNotFound));
+        };
+        let member = object
+            .members
+            .iter_mut()
+            .rfind(|m| m.key() == last)
+            .ok_or_else(|| Error::new(ErrorCode::PathNotFound))?;
+        let mut key = render_key(new_key)?;
+        key.leading = mem::take(&mut member.key.leading);
+        key.trailing = mem::take(&mut member.key.trailing);
+        member.key = key;
+        Ok(())
+    }
+
+    // The parent of the value at the path, the last segment of the path, and the indentation of
+    // the line the parent starts on.
+    fn parent_mut<'a>(
+        &mut self,
+        segments: &'a [Cow<str>],
+    ) -> Result<(&mut cst::Value, &'a str, String)> {
+        let (last, parent) = segments
+            .split_last()
+            .ok_or_else(|| Error::new(ErrorCode::PathNotFound))?;
+        let (parent, indent) = find_mut(&mut self.cst.value, parent, String::new())
+            .ok_or_else(|| Error::new(ErrorCode::PathNotFound))?;
+        Ok((parent, last, indent))
+    }
+}
+
+impl From<cst::Document> for Document {
+    fn from(cst: cst::Document) -> Self {
+        let newline = if cst.to_string().contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let indent = detect_indent(&cst.value, "").unwrap_or(Indent::Spaces(2));
+        Self {
+            cst,
+            newline,
+            indent,
+        }
+    }
+}
+
+impl FromStr for Document {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        cst::parse(s).map(Self::from)
+    }
+}
+
+impl Display for Document {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.cst)
+    }
+}
+
+// Splits a path into its segments, as described in the module docs.
+fn segments(path: &str) -> Result<Vec<Cow<'_, str>>> {
+    let invalid = || Error::new(ErrorCode::PathNotFound);
+    let mut segments = Vec::new();
+    let mut rest = path;
+    while !rest.is_empty() {
+        let segment;
+        (segment, rest) = if let Some(bracketed) = rest.strip_prefix('[') {
+            bracketed_segment(bracketed).ok_or_else(invalid)?
+        } else {
+            let (segment, rest) = rest.split_at(rest.find(['.', '[']).unwrap_or(rest.len()));
+            (Cow::Borrowed(segment), rest)
+        };
+        segments.push(segment);
+        if let Some(after) = rest.strip_prefix('.') {
+            rest = after;
+            if rest.is_empty() {
+                // A trailing dot is followed by an empty key.
+                segments.push(Cow::Borrowed(""));
+            }
+        } else if !rest.is_empty() && !rest.starts_with('[') {
+            return Err(invalid());
+        }
+    }
+    Ok(segments)
+}
+
+// Parses a quoted key or an index from just after a `[`, returning it and the rest of the path
+// after the `]`.
+fn bracketed_segment(s: &str) -> Option<(Cow<'_, str>, &str)> {
+    let quote = s.chars().next().filter(|&c| c == '"' || c == '\'');
+    let (segment, rest) = if let Some(quote) = quote {
+        let mut escaped = false;
+        let (end, _) = s.char_indices().skip(1).find(|&(_, c)| {
+            let end = !escaped && c == quote;
+            escaped = !escaped && c == '\\';
+            end
+        })?;
+        let (string, rest) = s.split_at(end + 1);
+        (Cow::Owned(crate::from_str(string).ok()?), rest)
+    } else {
+        let end = s
+            .find(|c: char| !c.is_ascii_digit())
+            .filter(|&end| end > 0)?;
+        let (index, rest) = s.split_at(end);
+        (Cow::Borrowed(index), rest)
+    };
+    Some((segment, rest.strip_prefix(']')?))
+}
+
+// Finds the value at the path, along with the indentation of the line it starts on.
+fn find_mut<'a>(
+    value: &'a mut cst::Value,
+    segments: &[Cow<str>],
+    indent: String,
+) -> Option<(&'a mut cst::Value, String)> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return Some((value, indent));
+    };
+    let multiline = is_multiline(value);
+    // Entries in an inline collection don't start lines of their own.
+    let entry_indent = |first_token: &Token| {
+        if multiline {
+            indentation(first_token).to_owned()
+        } else {
+            indent.clone()
+        }
+    };
+    let (value, indent) = match value {
+        cst::Value::Scalar(_) => return None,
+        cst::Value::Array(array) => {
+            let element = array.elements.get_mut(segment.parse::<usize>().ok()?)?;
+            let indent = entry_indent(element.value.first_token());
+            (&mut element.value, indent)
+        }
+        cst::Value::Object(object) => {
+            let member = object
+                .members
+                .iter_mut()
+                .rfind(|member| member.key() == *segment)?;
+            let indent = entry_indent(&member.key);
+            (&mut member.value, indent)
+        }
+    };
+    find_mut(value, rest, indent)
+}
+
+// Serializes the value, indenting every line after the first.
+fn render<T: ?Sized + Serialize>(
+    value: &T,
+    indent: &str,
+    newline: &str,
+    unit: Indent,
+) -> Result<cst::Value> {
+    let config = SerializerConfig {
+        indent: unit,
+        ..SerializerConfig::default()
+    };
+    let s = crate::to_string_with(value, &config)?.replace('\n', &format!("{newline}{indent}"));
+    Ok(cst::parse(&s)?.value)
+}
+
+// The indentation for each level of nesting: the difference between the indentation of the first
+// entry in a multiline collection and that of the line the collection starts on, if it's a tab or
+// some spaces.
+fn detect_indent(value: &cst::Value, outer: &str) -> Option<Indent> {
+    fn search<E: Entry>(
+        entries: &[E],
+        outer: &str,
+        multiline: bool,
+        value: impl Fn(&E) -> &cst::Value,
+    ) -> Option<Indent> {
+        entries.iter().find_map(|entry| {
+            let inner = indentation(entry.first_token());
+            if !multiline {
+                return detect_indent(value(entry), outer);
+            }
+            let unit = inner.strip_prefix(outer).unwrap_or_default();
+            match unit.len() {
+                0 => None,
+                1 if unit == "\t" => Some(Indent::Tabs),
+                n if unit.bytes().all(|b| b == b' ') => Some(Indent::Spaces(n)),
+                _ => None,
+            }
+            .or_else(|| detect_indent(value(entry), inner))
+        })
+    }
+    let multiline = is_multiline(value);
+    match value {
+        cst::Value::Scalar(_) => None,
+        cst::Value::Array(array) => search(&array.elements, outer, multiline, |e| &e.value),
+        cst::Value::Object(object) => search(&object.members, outer, multiline, |m| &m.value),
+    }
+}
+
+// Renders a key the same way the serializer does: without quotes where possible.
+fn render_key(key: &str) -> Result<Token> {
+    if crate::char::is_json5_identifier_name(key) {
+        Ok(Token::new(TokenKind::Identifier, key))
+    } else {
+        Ok(Token::new(TokenKind::String, crate::to_string(key)?))
+    }
+}
+
+// Replaces a value, keeping the trivia around the old one.
+fn replace(old: &mut cst::Value, mut new: cst::Value) {
+    new.first_token_mut().leading = mem::take(&mut old.first_token_mut().leading);
+    new.last_token_mut().trailing = mem::take(&mut old.last_token_mut().trailing);
+    *old = new;
+}
+
+fn whitespace(text: &str) -> Vec<Trivia> {
+    if text.is_empty() {
+        Vec::new()
+    } else {
+        vec![Trivia::new(TriviaKind::Whitespace, text)]
+    }
+}
+
+fn has_newline(trivia: &[Trivia]) -> bool {
+    trivia.iter().any(|t| t.kind == TriviaKind::Newline)
+}
+
+// The whitespace directly before a token, which is its indentation if it starts a line.
+fn indentation(token: &Token) -> &str {
+    match token.leading.last() {
+        Some(trivia) if trivia.kind == TriviaKind::Whitespace => &trivia.text,
+        _ => "",
+    }
+}
+
+fn is_multiline(value: &cst::Value) -> bool {
+    fn check<E: Entry>(open: &Token, entries: &[E], close: &Token) -> bool {
+        has_newline(&open.trailing)
+            || has_newline(&close.leading)
+            || entries.iter().any(|e| {
+                has_newline(&e.first_token().leading) || has_newline(&e.last_token().trailing)
+            })
+    }
+    match value {
+        cst::Value::Scalar(_) => false,
+        cst::Value::Array(array) => check(&array.open, &array.elements, &array.close),
+        cst::Value::Object(object) => check(&object.open, &object.members, &object.close),
+    }
+}
+
+// An element of an array or a member of an object.
+trait Entry {
+    fn first_token(&self) -> &Token;
+    fn first_token_mut(&mut self) -> &mut Token;
+    fn value_mut(&mut self) -> &mut cst::Value;
+    fn comma_mut(&mut self) -> &mut Option<Token>;
+
+    fn last_token(&self) -> &Token;
+
+    fn last_token_mut(&mut self) -> &mut Token {
+        if self.comma_mut().is_some() {
+            self.comma_mut().as_mut().expect("comma is some")
+        } else {
+            self.value_mut().last_token_mut()
+        }
+    }
+}
+
+impl Entry for Element {
+    fn first_token(&self) -> &Token {
+        self.value.first_token()
+    }
+
+    fn first_token_mut(&mut self) -> &mut Token {
+        self.value.first_token_mut()
+    }
+
+    fn value_mut(&mut self) -> &mut cst::Value {
+        &mut self.value
+    }
+
+    fn comma_mut(&mut self) -> &mut Option<Token> {
+        &mut self.comma
+    }
+
+    fn last_token(&self) -> &Token {
+        self.comma
+            .as_ref()
+            .unwrap_or_else(|| self.value.last_token())
+    }
+}
+
+impl Entry for Member {
+    fn first_token(&self) -> &Token {
+        &self.key
+    }
+
+    fn first_token_mut(&mut self) -> &mut Token {
+        &mut self.key
+    }
+
+    fn value_mut(&mut self) -> &mut cst::Value {
+        &mut self.value
+    }
+
+    fn comma_mut(&mut self) -> &mut Option<Token> {
+        &mut self.comma
+    }
+
+    fn last_token(&self) -> &Token {
+        self.comma
+            .as_ref()
+            .unwrap_or_else(|| self.value.last_token())
+    }
+}
+
+// The indentation for a new entry at the given index, copied from its neighbours.
+fn entry_indent<E: Entry>(
+    entries: &[E],
+    index: usize,
+    indent: &str,
+    multiline: bool,
+    unit: Indent,
+) -> String {
+    if entries.is_empty() {
+        match unit {
+            Indent::Spaces(n) => format!("{indent}{:n$}", ""),
+            Indent::Tabs => format!("{indent}\t"),
+        }
+    } else if multiline {
+        indentation(entries[index.min(entries.len() - 1)].first_token()).to_owned()
+    } else {
+        indent.to_owned()
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Layout<'a> {
+    multiline: bool,
+    // The indentation of the line the collection starts on.
+    indent: &'a str,
+    entry_indent: &'a str,
+    newline: &'a str,
+}
+
+// Inserts an entry, laying it out like the entries around it.
+fn insert_entry<E: Entry>(
+    open: &mut Token,
+    entries: &mut Vec<E>,
+    close: &mut Token,
+    index: usize,
+    mut entry: E,
+    layout: Layout,
+) {
+    let newline = || vec![Trivia::new(TriviaKind::Newline, layout.newline)];
+    let comma = |trailing| {
+        let mut comma = Token::new(TokenKind::Comma, ",");
+        comma.trailing = trailing;
+        comma
+    };
+
+    if entries.is_empty() {
+        // Lay the collection out over multiple lines, as the serializer would.
+        if !has_newline(&open.trailing) {
+            open.trailing.retain(|t| t.kind != TriviaKind::Whitespace);
+            open.trailing.extend(newline());
+        }
+        if close
+            .leading
+            .iter()
+            .all(|t| t.kind == TriviaKind::Whitespace)
+        {
+            close.leading = whitespace(layout.indent);
+        }
+        entry.first_token_mut().leading = whitespace(layout.entry_indent);
+        *entry.comma_mut() = Some(comma(newline()));
+    } else if layout.multiline {
+        entry.first_token_mut().leading = whitespace(layout.entry_indent);
+        if index < entries.len() {
+            *entry.comma_mut() = Some(comma(newline()));
+        } else {
+            let last = entries.last_mut().expect("entries is non-empty");
+            // Follow the last entry's lead on trailing commas.
+            if last.comma_mut().is_some() {
+                *entry.comma_mut() = Some(comma(newline()));
+            } else {
+                let trailing = mem::take(&mut last.value_mut().last_token_mut().trailing);
+                *last.comma_mut() = Some(comma(trailing));
+                entry.value_mut().last_token_mut().trailing = newline();
+            }
+            // Make sure the new entry starts on a line of its own.
+            let trailing = &mut last.last_token_mut().trailing;
+            if !has_newline(trailing) {
+                trailing.retain(|t| t.kind != TriviaKind::Whitespace);
+                trailing.extend(newline());
+            }
+        }
+    } else {
+        // Copy the spacing between existing entries.
+        let compact = entries.len() > 1
+            && entries[0].last_token().trailing.is_empty()
+            && entries[1].first_token().leading.is_empty();
+        let separator = whitespace(if compact { "" } else { " " });
+        if index < entries.len() {
+            *entry.comma_mut() = Some(comma(separator));
+        } else {
+            let last = entries.last_mut().expect("entries is non-empty");
+            entry.first_token_mut().leading = separator;
+            if let Some(last_comma) = last.comma_mut() {
+                *entry.comma_mut() = Some(comma(mem::take(&mut last_comma.trailing)));
+            } else {
+                entry.value_mut().last_token_mut().trailing =
+                    mem::take(&mut last.value_mut().last_token_mut().trailing);
+                *last.comma_mut() = Some(comma(Vec::new()));
+            }
+        }
+    }
+    entries.insert(index, entry);
+}
+
+// Removes an entry, along with the comments before it.
+fn remove_entry<E: Entry>(entries: &mut Vec<E>, index: usize) -> E {
+    let mut entry = entries.remove(index);
+    // If we removed the last entry and it didn't have a trailing comma, the new last entry
+    // shouldn't have one either.
+    if entry.comma_mut().is_none()
+        && index > 0
+        && let Some(comma) = entries[index - 1].comma_mut().take()
+    {
+        let trailing = if comma
+            .trailing
+            .iter()
+            .any(|t| t.kind != TriviaKind::Whitespace)
+        {
+            comma.trailing
+        } else {
+            mem::take(&mut entry.value_mut().last_token_mut().trailing)
+        };
+        entries[index - 1].value_mut().last_token_mut().trailing = trailing;
+    }
+    entry
+}
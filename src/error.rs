@@ -1,9 +1,16 @@
-use std::fmt::{Display, Formatter};
+use std::{
+    fmt::{Display, Formatter},
+    ops::Range,
+};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// An error serializing or deserializing JSON5.
-#[derive(Debug, PartialEq, Clone)]
+///
+/// Two errors are equal if they have the same content and [`Position`]. Spans and paths aren't
+/// compared, so check [`span`](Error::span) and [`path`](Error::path) separately where they
+/// matter.
+#[derive(Debug, Clone)]
 pub struct Error {
     inner: Box<ErrorInner>,
 }
@@ -21,12 +28,48 @@ impl Error {
     pub fn position(&self) -> Option<Position> {
         self.inner.position
     }
+
+    /// The byte offset in the input that the error occured at. Always the start of
+    /// [`span`](Error::span).
+    #[must_use]
+    pub fn offset(&self) -> Option<usize> {
+        self.span().map(|span| span.start)
+    }
+
+    /// The range of bytes in the input that the error refers to. This is the whole of the
+    /// offending token where we know its extent (e.g. a number that's out of range, or a
+    /// duplicate key), and otherwise just the offending character.
+    #[must_use]
+    pub fn span(&self) -> Option<Range<usize>> {
+        self.inner.span.clone()
+    }
+
+    /// Where in the document the error occured, as a path of object keys and array indices from
+    /// the root (e.g. `servers[3].tls.port`). `None` if the error occured at the root, or if it
+    /// didn't occur while deserializing.
+    #[must_use]
+    pub fn path(&self) -> Option<&str> {
+        if self.inner.path.is_empty() {
+            None
+        } else {
+            Some(&self.inner.path)
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.content == other.inner.content && self.inner.position == other.inner.position
+    }
+}
+
+#[derive(Debug, Clone)]
 struct ErrorInner {
     content: ErrorContent,
     position: Option<Position>,
+    span: Option<Range<usize>>,
+    // Built up from the inside out as the error propagates.
+    path: String,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -38,6 +81,11 @@ enum ErrorContent {
 /// A code identifying an error originating within this crate.
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ErrorCode {
+    DuplicateKey {
+        /// Where the key first occurred. The error itself is positioned at the second occurrence.
+        first: Position,
+    },
+
     EofParsingArray,
     EofParsingBool,
     EofParsingComment,
@@ -64,18 +112,39 @@ pub enum ErrorCode {
     ExpectedStringOrObject,
     ExpectedValue,
 
+    CommentInJson,
+    ControlCharacterInJson,
+    EscapeSequenceInJson,
+    HexNumberInJson,
+    InfinityInJson,
+    LeadingDecimalPointInJson,
+    LineContinuationInJson,
+    NanInJson,
+    PlusSignInJson,
+    SingleQuotesInJson,
+    TrailingCommaInJson,
+    TrailingDecimalPointInJson,
+    UnquotedKeyInJson,
+    WhitespaceInJson,
+
     InvalidBytes,
     InvalidEscapeSequence,
     InvalidKey,
+    InvalidUtf8,
     LeadingZero,
     LineTerminatorInString,
     OverflowParsingNumber,
+    PathNotFound,
+    RecursionLimitExceeded,
     TrailingCharacters,
 }
 
 impl Display for ErrorCode {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
+            ErrorCode::DuplicateKey { first } => {
+                write!(f, "duplicate key (first occurrence at {first})")
+            }
             ErrorCode::EofParsingArray => write!(f, "EOF parsing array"),
             ErrorCode::EofParsingBool => write!(f, "EOF parsing bool"),
             ErrorCode::EofParsingComment => write!(f, "EOF parsing comment"),
@@ -102,12 +171,30 @@ impl Display for ErrorCode {
             ErrorCode::ExpectedStringOrObject => write!(f, "expected string or object"),
             ErrorCode::ExpectedValue => write!(f, "expected value"),
 
+            ErrorCode::CommentInJson => write!(f, "comment in JSON"),
+            ErrorCode::ControlCharacterInJson => write!(f, "unescaped control character in JSON"),
+            ErrorCode::EscapeSequenceInJson => write!(f, "JSON5 escape sequence in JSON"),
+            ErrorCode::HexNumberInJson => write!(f, "hexadecimal number in JSON"),
+            ErrorCode::InfinityInJson => write!(f, "Infinity in JSON"),
+            ErrorCode::LeadingDecimalPointInJson => write!(f, "leading decimal point in JSON"),
+            ErrorCode::LineContinuationInJson => write!(f, "line continuation in JSON"),
+            ErrorCode::NanInJson => write!(f, "NaN in JSON"),
+            ErrorCode::PlusSignInJson => write!(f, "plus sign in JSON"),
+            ErrorCode::SingleQuotesInJson => write!(f, "single quoted string in JSON"),
+            ErrorCode::TrailingCommaInJson => write!(f, "trailing comma in JSON"),
+            ErrorCode::TrailingDecimalPointInJson => write!(f, "trailing decimal point in JSON"),
+            ErrorCode::UnquotedKeyInJson => write!(f, "unquoted key in JSON"),
+            ErrorCode::WhitespaceInJson => write!(f, "JSON5 whitespace in JSON"),
+
             ErrorCode::InvalidBytes => write!(f, "invalid bytes"),
             ErrorCode::InvalidEscapeSequence => write!(f, "invalid escape sequence"),
             ErrorCode::InvalidKey => write!(f, "invalid key"),
+            ErrorCode::InvalidUtf8 => write!(f, "invalid UTF-8"),
             ErrorCode::LeadingZero => write!(f, "leading zero"),
             ErrorCode::LineTerminatorInString => write!(f, "line terminator in string"),
             ErrorCode::OverflowParsingNumber => write!(f, "overflow parsing number"),
+            ErrorCode::PathNotFound => write!(f, "path not found"),
+            ErrorCode::RecursionLimitExceeded => write!(f, "recursion limit exceeded"),
             ErrorCode::TrailingCharacters => write!(f, "trailing characters"),
         }
     }
@@ -124,6 +211,9 @@ impl Display for ErrorContent {
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        if let Some(path) = self.path() {
+            write!(f, "{path}: ")?;
+        }
         if let Some(position) = self.inner.position {
             write!(f, "{} at {}", self.inner.content, position)
         } else {
@@ -132,6 +222,112 @@ impl Display for Error {
     }
 }
 
+/// Displays an [`Error`] along with the part of the source it refers to. Constructed with
+/// [`Error::display_with_source`].
+pub struct Diagnostic<'a> {
+    error: &'a Error,
+    source: &'a str,
+    filename: Option<&'a str>,
+}
+
+// The number of lines to show either side of the line the error occured on.
+const CONTEXT_LINES: usize = 2;
+
+impl Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "error: ")?;
+        if let Some(path) = self.error.path() {
+            write!(f, "{path}: ")?;
+        }
+        writeln!(f, "{}", self.error.inner.content)?;
+
+        let Some(position) = self.error.inner.position else {
+            if let Some(filename) = self.filename {
+                writeln!(f, " --> {filename}")?;
+            }
+            return Ok(());
+        };
+
+        let lines: Vec<&str> = crate::char::lines(self.source).collect();
+        let first = position.line.saturating_sub(CONTEXT_LINES);
+        let last = (position.line + CONTEXT_LINES).min(lines.len().saturating_sub(1));
+        let width = (last + 1).to_string().len();
+
+        write!(f, "{:width$}--> ", "")?;
+        if let Some(filename) = self.filename {
+            write!(f, "{filename}:")?;
+        }
+        writeln!(f, "{}:{}", position.line + 1, position.column + 1)?;
+
+        let Some(line) = lines.get(position.line) else {
+            return Ok(());
+        };
+
+        writeln!(f, "{:width$} |", "")?;
+        for (i, l) in lines.iter().enumerate().take(last + 1).skip(first) {
+            writeln!(f, "{:>width$} | {l}", i + 1)?;
+            if i == position.line {
+                // Copy tabs from the line so the underline lines up.
+                let indent: String = line
+                    .chars()
+                    .take(position.column)
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect();
+                let underline = self
+                    .error
+                    .span()
+                    .and_then(|span| self.source.get(span))
+                    .map_or(1, |s| {
+                        crate::char::lines(s)
+                            .next()
+                            .map_or(0, |s| s.chars().count())
+                    })
+                    .max(1);
+                writeln!(f, "{:width$} | {indent}{}", "", "^".repeat(underline))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Error {
+    /// Display the error along with the lines of `source` surrounding it, with the offending part
+    /// underlined. `source` should be the input which caused the error. The error's
+    /// [`path`](Error::path), if any, is shown before the message.
+    ///
+    /// # Example
+    /// ```
+    /// let source = "{\n  foo: 1,\n  bar 2,\n}";
+    /// let err = json5::from_str::<json5::Value>(source).unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     err.display_with_source(source, Some("config.json5")).to_string(),
+    ///     "\
+    /// error: expected colon
+    ///  --> config.json5:3:7
+    ///   |
+    /// 1 | {
+    /// 2 |   foo: 1,
+    /// 3 |   bar 2,
+    ///   |       ^
+    /// 4 | }
+    /// ",
+    /// );
+    /// ```
+    #[must_use]
+    pub fn display_with_source<'a>(
+        &'a self,
+        source: &'a str,
+        filename: Option<&'a str>,
+    ) -> Diagnostic<'a> {
+        Diagnostic {
+            error: self,
+            source,
+            filename,
+        }
+    }
+}
+
 impl Error {
     #[must_use]
     pub fn new(code: ErrorCode) -> Self {
@@ -139,6 +335,8 @@ impl Error {
             inner: Box::new(ErrorInner {
                 content: ErrorContent::Code(code),
                 position: None,
+                span: None,
+                path: String::new(),
             }),
         }
     }
@@ -149,6 +347,8 @@ impl Error {
             inner: Box::new(ErrorInner {
                 content: ErrorContent::Code(code),
                 position: Some(position),
+                span: None,
+                path: String::new(),
             }),
         }
     }
@@ -159,6 +359,8 @@ impl Error {
             inner: Box::new(ErrorInner {
                 content: ErrorContent::Custom(msg.to_string()),
                 position: None,
+                span: None,
+                path: String::new(),
             }),
         }
     }
@@ -169,6 +371,8 @@ impl Error {
             inner: Box::new(ErrorInner {
                 content: ErrorContent::Custom(msg.to_string()),
                 position: Some(position),
+                span: None,
+                path: String::new(),
             }),
         }
     }
@@ -180,6 +384,43 @@ impl Error {
         }
         self
     }
+
+    #[must_use]
+    pub(crate) fn with_index(self, index: usize) -> Self {
+        self.with_path_segment(format!("[{index}]"))
+    }
+
+    #[must_use]
+    pub(crate) fn with_key(self, key: &str) -> Self {
+        if crate::char::is_json5_identifier_name(key) {
+            self.with_path_segment(key.to_owned())
+        } else {
+            self.with_path_segment(format!("[{key:?}]"))
+        }
+    }
+
+    fn with_path_segment(mut self, mut segment: String) -> Self {
+        if !self.inner.path.is_empty() && !self.inner.path.starts_with('[') {
+            segment.push('.');
+        }
+        self.inner.path.insert_str(0, &segment);
+        self
+    }
+
+    #[must_use]
+    pub(crate) fn without_position(mut self) -> Self {
+        self.inner.position = None;
+        self.inner.span = None;
+        self
+    }
+
+    #[must_use]
+    pub(crate) fn with_span(mut self, span: Range<usize>) -> Self {
+        if self.inner.span.is_none() {
+            self.inner.span = Some(span);
+        }
+        self
+    }
 }
 
 impl serde::de::Error for Error {
@@ -214,8 +455,13 @@ pub struct Position {
 impl Position {
     #[must_use]
     pub fn from_offset(offset: usize, input: &str) -> Self {
-        let mut res = Self { line: 0, column: 0 };
-        let mut chars = input[..offset].chars().peekable();
+        Self { line: 0, column: 0 }.advance(&input[..offset])
+    }
+
+    // The position after `text`, given that it starts at this position. `text` shouldn't end
+    // between a CR and LF.
+    pub(crate) fn advance(mut self, text: &str) -> Self {
+        let mut chars = text.chars().peekable();
         while let Some(c) = chars.next() {
             if crate::char::is_json5_line_terminator(c) {
                 // "The character sequence <CR><LF> is commonly used as a line terminator. It
@@ -224,13 +470,13 @@ impl Position {
                 if c == '\u{000D}' && chars.peek() == Some(&'\u{000A}') {
                     chars.next();
                 }
-                res.line += 1;
-                res.column = 0;
+                self.line += 1;
+                self.column = 0;
             } else {
-                res.column += 1;
+                self.column += 1;
             }
         }
-        res
+        self
     }
 }
 
@@ -50,6 +50,10 @@
 //! # Ok::<(), json5::Error>(())
 //! ```
 //!
+//! To parse bytes or read from a file instead, use [`from_slice`] or [`from_reader`]. To check a
+//! document for mistakes without deserializing it, use [`validate`], which reports every syntax
+//! error rather than just the first.
+//!
 //! There are many ways to customize the deserialization (e.g. deserializing `camelCase` field names
 //! into a struct with `snake_case` fields). See the Serde docs, especially the [Attributes][],
 //! [Custom serialization][], and [Examples][] sections.
@@ -58,8 +62,18 @@
 //!
 //! Similarly, implementing [`serde::Serialize`] on a Rust type allows you to produce a JSON5
 //! serialization of values of that type with [`to_string`] or [`to_writer`]. The serializer will
-//! omit quotes around object keys where possible and will indent nested objects and arrays, but is
-//! otherwise fairly basic.
+//! omit quotes around object keys where possible and will indent nested objects and arrays. Use
+//! [`to_string_with`] and a [`SerializerConfig`] to change the indentation, line endings, trailing
+//! commas or quotes, to sort object keys, to write integers in hexadecimal or floats with a fixed
+//! number of decimals, to keep arrays and objects on one line when they fit within a maximum width,
+//! or to write everything compactly on a single line. Setting its `dialect` to [`Dialect::Json`]
+//! writes plain JSON instead, for consumers that don't understand JSON5. Wrap a value in
+//! [`Commented`] to write a comment before it, or in [`ser::Hex`] to write its integers in
+//! hexadecimal.
+//!
+//! With the `derive` feature enabled, `#[derive(Documented)]` writes the doc comments on a struct's
+//! fields as comments too, and [`default_config`] uses it to generate a commented config file from
+//! the struct's defaults. See [`Documented`].
 //!
 //! ```
 //! use serde_derive::Serialize;
@@ -86,10 +100,55 @@
 //! as `camelCase`). See the Serde docs, especially the [Attributes][], [Custom serialization][] and
 //! [Examples][] sections.
 //!
+//! # Untyped values
+//!
+//! If you don't know the shape of your data up front, parse it into a [`Value`]. Unlike
+//! `serde_json::Value`, [`Value`] can hold any number JSON5 can express, including `Infinity`,
+//! `NaN`, and integers across the full range of [`u128`] and [`i128`]. Object keys are sorted, unless
+//! the `preserve_order` feature is enabled, in which case they keep the order they were written in.
+//!
+//! ```
+//! use json5::Value;
+//!
+//! let value: Value = "{ big: 0xffffffffffffffffffffffffffffffff, ratio: NaN }".parse()?;
+//!
+//! assert_eq!(value["big"].as_number().and_then(|n| n.as_u128()), Some(u128::MAX));
+//! assert!(value["ratio"].as_f64().is_some_and(f64::is_nan));
+//! # Ok::<(), json5::Error>(())
+//! ```
+//!
+//! The [`json5!`] macro builds a [`Value`] from JSON5 syntax, interpolating Rust expressions.
+//!
+//! ```
+//! use json5::json5;
+//!
+//! let ratio = 0.5;
+//! let value = json5!({ big: 0xff, 'r': ratio, list: [.5, Infinity,] });
+//! assert_eq!(value, "{ big: 255, r: 0.5, list: [0.5, Infinity] }".parse()?);
+//! # Ok::<(), json5::Error>(())
+//! ```
+//!
+//! # Preserving formatting
+//!
+//! Deserializing throws away comments and formatting. To inspect or modify a hand-written document
+//! without disturbing them, parse it with [`cst::parse`], which produces a lossless syntax tree
+//! that prints back exactly as it was written. For simple edits by path, use an
+//! [`edit::Document`].
+//!
+//! ```
+//! let input = "{ version: '1.0.0', /* keep me */ }";
+//! assert_eq!(json5::cst::parse(input)?.to_string(), input);
+//!
+//! let mut doc: json5::edit::Document = input.parse()?;
+//! doc.set("version", "1.1.0")?;
+//! assert_eq!(doc.to_string(), "{ version: \"1.1.0\", /* keep me */ }");
+//! # Ok::<(), json5::Error>(())
+//! ```
+//!
 //! # Byte arrays
 //!
-//! All the types of the [Serde data model][] are supported. Byte arrays are encoded as hex strings.
-//! e.g.
+//! All the types of the [Serde data model][] are supported. Byte arrays are encoded as hex strings
+//! by default, e.g.
 //!
 //! ```
 //! use serde_bytes::{Bytes, ByteBuf};
@@ -100,6 +159,10 @@
 //! # Ok::<(), json5::Error>(())
 //! ```
 //!
+//! Set [`SerializerConfig::bytes`] and [`ParseOptions::bytes`] to a [`ByteEncoding`] to use base64
+//! or arrays of numbers instead. Set [`ParseOptions::detect_bytes`] to accept arrays of numbers as
+//! well as strings.
+//!
 //! [Attributes]: https://serde.rs/attributes.html
 //! [Custom serialization]: https://serde.rs/custom-serialization.html
 //! [ECMAScript 5.1]: https://www.ecma-international.org/ecma-262/5.1/
@@ -114,13 +177,41 @@
 
 #[macro_use]
 mod de;
+mod bytes;
 mod char;
+mod commented;
+pub mod cst;
+mod documented;
+pub mod edit;
 mod error;
-mod ser;
+mod macros;
+pub mod ser;
+mod value;
 
 #[allow(clippy::all, clippy::pedantic, dead_code)]
 mod unicode;
 
-pub use de::{Deserializer, from_str};
-pub use error::{Error, ErrorCode, Position};
-pub use ser::{Serializer, to_string, to_writer};
+pub use bytes::ByteEncoding;
+pub use commented::Commented;
+pub use de::{
+    Deserializer, Dialect, DuplicateKeys, ParseOptions, StreamDeserializer, from_reader,
+    from_slice, from_str, validate,
+};
+pub use documented::{Documented, WithDocs, default_config};
+pub use error::{Diagnostic, Error, ErrorCode, Position};
+pub use ser::{
+    FloatFormat, Indent, IntegerFormat, LineEnding, NonFinite, Quote, Serializer, SerializerConfig,
+    TrailingCommas, natural_order, to_string, to_string_with, to_writer, to_writer_with,
+};
+pub use value::{Map, Number, Value, from_value, from_value_with, to_value, to_value_with};
+
+#[cfg(feature = "derive")]
+pub use json5_derive::Documented;
+
+// Used by the `json5!` macro and `#[derive(Documented)]`. Not public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::documented::{Probe, ViaDocumented, ViaSerialize};
+    pub use crate::macros::{Key, identifier_key, is_fraction, number};
+    pub use serde;
+}
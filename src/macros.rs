@@ -0,0 +1,484 @@
+/// Construct a [`Value`](crate::Value) from a JSON5 literal.
+///
+/// ```
+/// use json5::{Value, json5};
+///
+/// let port = 8080;
+/// let value = json5!({
+///     // Keys can be unquoted identifiers, strings, or (parenthesized) Rust expressions.
+///     name: "server",
+///     'x': 0xff,
+///     ("a".repeat(3)): [Infinity, -.5, +1,],
+///     port: port + 1,
+///     tls: null,
+/// });
+///
+/// assert_eq!(value["name"], Value::from("server"));
+/// assert_eq!(value["x"], Value::from(255));
+/// assert_eq!(value["aaa"][0], Value::from(f64::INFINITY));
+/// assert_eq!(value["aaa"][1], Value::from(-0.5));
+/// assert_eq!(value["port"], Value::from(8081));
+/// assert_eq!(value, "{ name: 'server', x: 0xff, aaa: [Infinity, -.5, +1], port: 8081, tls: null }".parse()?);
+/// # Ok::<(), json5::Error>(())
+/// ```
+///
+/// Literals are converted directly, and unquoted keys and numbers with a leading decimal point
+/// are checked at compile time. Anything that isn't JSON5 syntax is interpolated as a Rust
+/// expression and converted with [`to_value`](crate::to_value), so it can be any type implementing
+/// [`Serialize`](serde::Serialize).
+///
+/// ```compile_fail
+/// // `·` can be part of a Rust identifier, but not a JSON5 one.
+/// let value = json5::json5!({ a·b: 1 });
+/// ```
+///
+/// Since the input has to be valid Rust tokens, there are some limitations:
+///
+/// - Single quoted strings only work if they're a single character, since anything else isn't a
+///   valid Rust token.
+/// - Numbers are Rust literals, so integers that don't fit in an `i32` need a type suffix (e.g.
+///   `0xdeadbeef_u32`).
+/// - Expressions containing a comma outside of any brackets (e.g. `HashMap::<u8, u8>::new()`)
+///   need to be wrapped in parentheses.
+///
+/// # Panics
+/// Panics if an interpolated expression can't be converted to a [`Value`](crate::Value).
+#[macro_export]
+macro_rules! json5 {
+    ($($json5:tt)+) => {
+        $crate::json5_internal!($($json5)+)
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! json5_internal {
+    //////////////////////////////////////////////////////////////////////////
+    // Parse the elements of an array, munching one element at a time.
+    //
+    // Must be invoked as: json5_internal!(@array [] $($tt)*)
+    //////////////////////////////////////////////////////////////////////////
+
+    // Done with trailing comma.
+    (@array [$($elems:expr,)*]) => {
+        ::std::vec![$($elems,)*]
+    };
+
+    // Done without trailing comma.
+    (@array [$($elems:expr),*]) => {
+        ::std::vec![$($elems),*]
+    };
+
+    // Next element is a keyword, or a number that isn't a Rust expression.
+    (@array [$($elems:expr,)*] null $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!(null)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] true $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!(true)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] false $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!(false)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] Infinity $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!(Infinity)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] NaN $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!(NaN)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] . $frac:literal $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!(. $frac)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $sign:tt Infinity $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!($sign Infinity)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $sign:tt NaN $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!($sign NaN)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $sign:tt . $frac:literal $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!($sign . $frac)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] + $lit:literal $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!(+ $lit)] $($rest)*)
+    };
+
+    // Next element is a literal. A `literal` fragment includes a leading `-`, but it's an error
+    // (rather than a failed match) if that isn't followed by a literal, so anything else starting
+    // with `-` is marked as an expression before it gets that far.
+    (@array [$($elems:expr,)*] - - $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)*] @expr - - $($rest)*)
+    };
+    (@array [$($elems:expr,)*] - $lit:literal , $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!(- $lit),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] - $lit:literal) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!(- $lit)])
+    };
+    (@array [$($elems:expr,)*] - $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)*] @expr - $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $lit:literal , $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!($lit),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $lit:literal) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!($lit)])
+    };
+
+    // Next element is an array.
+    (@array [$($elems:expr,)*] [$($array:tt)*] $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!([$($array)*])] $($rest)*)
+    };
+
+    // Next element is an object.
+    (@array [$($elems:expr,)*] {$($object:tt)*} $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!({$($object)*})] $($rest)*)
+    };
+
+    // Next element is an expression followed by comma.
+    (@array [$($elems:expr,)*] $(@expr)? $next:expr, $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!($next),] $($rest)*)
+    };
+
+    // Last element is an expression with no trailing comma.
+    (@array [$($elems:expr,)*] $(@expr)? $last:expr) => {
+        $crate::json5_internal!(@array [$($elems,)* $crate::json5_internal!($last)])
+    };
+
+    // Comma after the most recent element.
+    (@array [$($elems:expr),+] , $($rest:tt)*) => {
+        $crate::json5_internal!(@array [$($elems,)*] $($rest)*)
+    };
+
+    // Unexpected token after most recent element.
+    (@array [$($elems:expr),*] $unexpected:tt $($rest:tt)*) => {
+        $crate::json5_unexpected!($unexpected)
+    };
+
+    //////////////////////////////////////////////////////////////////////////
+    // Parse the members of an object, munching one key or value at a time.
+    //
+    // Must be invoked as: json5_internal!(@object $map () ($($tt)*))
+    //
+    // The first parenthesized group holds the key once it's been parsed. The second holds the
+    // remaining input.
+    //////////////////////////////////////////////////////////////////////////
+
+    // Done.
+    (@object $object:ident () ()) => {};
+
+    // Insert the current entry followed by trailing comma.
+    (@object $object:ident [$key:expr] ($value:expr) , $($rest:tt)*) => {
+        let _ = $object.insert($key, $value);
+        $crate::json5_internal!(@object $object () ($($rest)*));
+    };
+
+    // Insert the last entry without trailing comma.
+    (@object $object:ident [$key:expr] ($value:expr)) => {
+        let _ = $object.insert($key, $value);
+    };
+
+    // Unexpected token after the current entry.
+    (@object $object:ident [$key:expr] ($value:expr) $unexpected:tt $($rest:tt)*) => {
+        $crate::json5_unexpected!($unexpected);
+    };
+
+    // Next value is a keyword, or a number that isn't a Rust expression.
+    (@object $object:ident ($key:expr) (: null $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!(null)) $($rest)*);
+    };
+    (@object $object:ident ($key:expr) (: true $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!(true)) $($rest)*);
+    };
+    (@object $object:ident ($key:expr) (: false $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!(false)) $($rest)*);
+    };
+    (@object $object:ident ($key:expr) (: Infinity $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!(Infinity)) $($rest)*);
+    };
+    (@object $object:ident ($key:expr) (: NaN $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!(NaN)) $($rest)*);
+    };
+    (@object $object:ident ($key:expr) (: . $frac:literal $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!(. $frac)) $($rest)*);
+    };
+    (@object $object:ident ($key:expr) (: $sign:tt Infinity $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!($sign Infinity)) $($rest)*);
+    };
+    (@object $object:ident ($key:expr) (: $sign:tt NaN $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!($sign NaN)) $($rest)*);
+    };
+    (@object $object:ident ($key:expr) (: $sign:tt . $frac:literal $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!($sign . $frac)) $($rest)*);
+    };
+    (@object $object:ident ($key:expr) (: + $lit:literal $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!(+ $lit)) $($rest)*);
+    };
+
+    // Next value is a literal. See the corresponding array elements.
+    (@object $object:ident ($key:expr) (: - - $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object ($key) @expr (: - - $($rest)*));
+    };
+    (@object $object:ident ($key:expr) (: - $lit:literal , $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!(- $lit)) , $($rest)*);
+    };
+    (@object $object:ident ($key:expr) (: - $lit:literal)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!(- $lit)));
+    };
+    (@object $object:ident ($key:expr) (: - $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object ($key) @expr (: - $($rest)*));
+    };
+    (@object $object:ident ($key:expr) (: $lit:literal , $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!($lit)) , $($rest)*);
+    };
+    (@object $object:ident ($key:expr) (: $lit:literal)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!($lit)));
+    };
+
+    // Next value is an array.
+    (@object $object:ident ($key:expr) (: [$($array:tt)*] $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!([$($array)*])) $($rest)*);
+    };
+
+    // Next value is an object.
+    (@object $object:ident ($key:expr) (: {$($map:tt)*} $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!({$($map)*})) $($rest)*);
+    };
+
+    // Next value is an expression followed by comma.
+    (@object $object:ident ($key:expr) $(@expr)? (: $value:expr , $($rest:tt)*)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!($value)) , $($rest)*);
+    };
+
+    // Last value is an expression with no trailing comma.
+    (@object $object:ident ($key:expr) $(@expr)? (: $value:expr)) => {
+        $crate::json5_internal!(@object $object [$key] ($crate::json5_internal!($value)));
+    };
+
+    // Missing value for last entry.
+    (@object $object:ident ($key:expr) (:)) => {
+        // "unexpected end of macro invocation"
+        $crate::json5_internal!();
+    };
+
+    // Anything else after the key is an error.
+    (@object $object:ident ($key:expr) ($unexpected:tt $($rest:tt)*)) => {
+        $crate::json5_unexpected!($unexpected);
+    };
+
+    // Key is an identifier, which is checked at compile time.
+    (@object $object:ident () ($key:ident $($rest:tt)*)) => {
+        $crate::json5_internal!(
+            @object $object ({
+                const KEY: &str = $crate::__private::identifier_key(::std::stringify!($key));
+                ::std::string::String::from(KEY)
+            }) ($($rest)*)
+        );
+    };
+
+    // Key is a string (or character) literal.
+    (@object $object:ident () ($key:literal $($rest:tt)*)) => {
+        $crate::json5_internal!(
+            @object $object ($crate::__private::Key::into_key($key)) ($($rest)*)
+        );
+    };
+
+    // Key is an interpolated expression.
+    (@object $object:ident () (($key:expr) $($rest:tt)*)) => {
+        $crate::json5_internal!(
+            @object $object (::std::string::ToString::to_string(&$key)) ($($rest)*)
+        );
+    };
+
+    // Anything else where a key should be is an error.
+    (@object $object:ident () ($unexpected:tt $($rest:tt)*)) => {
+        $crate::json5_unexpected!($unexpected);
+    };
+
+    //////////////////////////////////////////////////////////////////////////
+    // The main implementation.
+    //
+    // Must be invoked as: json5_internal!($($json5)+)
+    //////////////////////////////////////////////////////////////////////////
+
+    (null) => {
+        $crate::Value::Null
+    };
+
+    (true) => {
+        $crate::Value::Bool(true)
+    };
+
+    (false) => {
+        $crate::Value::Bool(false)
+    };
+
+    (Infinity) => {
+        $crate::Value::from(f64::INFINITY)
+    };
+
+    (NaN) => {
+        $crate::Value::from(f64::NAN)
+    };
+
+    (+ Infinity) => {
+        $crate::Value::from(f64::INFINITY)
+    };
+
+    (- Infinity) => {
+        $crate::Value::from(-f64::INFINITY)
+    };
+
+    (+ NaN) => {
+        $crate::Value::from(f64::NAN)
+    };
+
+    (- NaN) => {
+        $crate::Value::from(-f64::NAN)
+    };
+
+    // Leading decimal points aren't valid Rust, so we parse these as JSON5 instead, having
+    // checked at compile time that they're valid.
+    (. $frac:literal) => {
+        $crate::json5_internal!(@fraction "." $frac)
+    };
+
+    (+ . $frac:literal) => {
+        $crate::json5_internal!(@fraction "." $frac)
+    };
+
+    (- . $frac:literal) => {
+        $crate::json5_internal!(@fraction "-." $frac)
+    };
+
+    (@fraction $point:literal $frac:literal) => {{
+        const _: () = ::std::assert!(
+            $crate::__private::is_fraction(::std::stringify!($frac)),
+            "expected digits after the decimal point",
+        );
+        $crate::__private::number(::std::concat!($point, ::std::stringify!($frac)))
+    }};
+
+    // Rust doesn't have a unary plus.
+    (+ $lit:literal) => {
+        $crate::json5_internal!($lit)
+    };
+
+    // Literals. See the corresponding array elements for why `-` is matched separately.
+    (- - $($tt:tt)+) => {
+        $crate::json5_internal!(@expr - - $($tt)+)
+    };
+
+    (- $lit:literal) => {
+        $crate::Value::from(-$lit)
+    };
+
+    (- $($tt:tt)+) => {
+        $crate::json5_internal!(@expr - $($tt)+)
+    };
+
+    ($lit:literal) => {
+        $crate::Value::from($lit)
+    };
+
+    ([]) => {
+        $crate::Value::Array(::std::vec![])
+    };
+
+    ([ $($tt:tt)+ ]) => {
+        $crate::Value::Array($crate::json5_internal!(@array [] $($tt)+))
+    };
+
+    ({}) => {
+        $crate::Value::Object($crate::Map::new())
+    };
+
+    ({ $($tt:tt)+ }) => {
+        $crate::Value::Object({
+            let mut object = $crate::Map::new();
+            $crate::json5_internal!(@object object () ($($tt)+));
+            object
+        })
+    };
+
+    // Any Serialize type: variables, function calls etc.
+    ($(@expr)? $other:expr) => {
+        $crate::to_value(&$other).expect("interpolated value can be converted to a json5::Value")
+    };
+}
+
+// Used to make a macro invocation fail on a particular token, so the compiler points to it.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! json5_unexpected {
+    () => {};
+}
+
+/// Unquoted keys are Rust identifiers, which are (almost) always JSON5 identifiers too. The macro
+/// calls this in a constant, so that a key which isn't fails to compile.
+///
+/// # Panics
+/// Panics if `key` isn't a valid JSON5 identifier.
+#[must_use]
+pub const fn identifier_key(key: &str) -> &str {
+    let key = match key.as_bytes() {
+        [b'r', b'#', ..] => key.split_at(2).1,
+        _ => key,
+    };
+    assert!(
+        crate::char::is_json5_identifier_name(key),
+        "this key isn't a valid JSON5 identifier, try quoting it",
+    );
+    key
+}
+
+/// Whether `s` can follow a leading decimal point in a JSON5 number, i.e. it's digits and maybe an
+/// exponent.
+#[must_use]
+pub const fn is_fraction(s: &str) -> bool {
+    let s = s.as_bytes();
+    let mut i = 0;
+    while i < s.len() && s[i].is_ascii_digit() {
+        i += 1;
+    }
+    if i == 0 {
+        return false;
+    }
+    if i < s.len() && matches!(s[i], b'e' | b'E') {
+        i += 1;
+        if i < s.len() && matches!(s[i], b'+' | b'-') {
+            i += 1;
+        }
+        let exponent = i;
+        while i < s.len() && s[i].is_ascii_digit() {
+            i += 1;
+        }
+        if i == exponent {
+            return false;
+        }
+    }
+    i == s.len()
+}
+
+/// # Panics
+/// Panics if `s` isn't a valid JSON5 number, which the macro checks at compile time with
+/// [`is_fraction`].
+#[must_use]
+pub fn number(s: &str) -> crate::Value {
+    crate::from_str(s).expect("the json5! macro only parses valid numbers")
+}
+
+/// Quoted keys can be strings or, if they're single quoted, chars.
+pub trait Key {
+    fn into_key(self) -> String;
+}
+
+impl Key for &str {
+    fn into_key(self) -> String {
+        self.to_owned()
+    }
+}
+
+impl Key for char {
+    fn into_key(self) -> String {
+        self.to_string()
+    }
+}
@@ -1,8 +1,16 @@
-use std::io::Write;
+//! Serializing Rust types as JSON5.
+//!
+//! Everything here apart from [`Hex`] is re-exported at the crate root. See [`SerializerConfig`]
+//! for the ways to format the output.
 
-use serde::{Serialize, ser::Impossible};
+use std::{
+    cmp::Ordering,
+    io::{self, Write},
+};
 
-use crate::{Error, ErrorCode, error::Result};
+use serde::{Deserialize, Deserializer, Serialize, ser::Impossible};
+
+use crate::{ByteEncoding, Dialect, Error, ErrorCode, bytes, commented, error::Result};
 
 /// Serialize a type implementing [`Serialize`] to a JSON5 string.
 ///
@@ -31,7 +39,7 @@ use crate::{Error, ErrorCode, error::Result};
 /// # Errors
 /// Fails if we can't express `T` in JSON5 (e.g. we try to serialize an object key without an
 /// obvious string representation).
-pub fn to_string<T: Serialize>(value: &T) -> Result<String> {
+pub fn to_string<T: ?Sized + Serialize>(value: &T) -> Result<String> {
     let mut w = Vec::new();
     to_writer(&mut w, value)?;
     #[expect(clippy::missing_panics_doc)]
@@ -43,26 +51,848 @@ pub fn to_string<T: Serialize>(value: &T) -> Result<String> {
 /// # Errors
 /// Fails if we can't express `T` in JSON5 (e.g. we try to serialize an object key without an
 /// obvious string representation) or if there's an error writing to the writer.
-pub fn to_writer<T: Serialize, W: Write>(w: W, value: &T) -> Result<()> {
+pub fn to_writer<T: ?Sized + Serialize, W: Write>(w: W, value: &T) -> Result<()> {
     value.serialize(&mut Serializer::new(w))
 }
 
+/// Serialize a type implementing [`Serialize`] to a JSON5 string, formatted according to the
+/// given config.
+///
+/// # Example
+/// ```
+/// use json5::{Indent, SerializerConfig};
+///
+/// let value = json5::json5!({ foo: [1, 2] });
+///
+/// let compact = SerializerConfig {
+///     compact: true,
+///     ..SerializerConfig::default()
+/// };
+/// assert_eq!(json5::to_string_with(&value, &compact)?, "{foo:[1,2]}");
+///
+/// let tabs = SerializerConfig {
+///     indent: Indent::Tabs,
+///     ..SerializerConfig::default()
+/// };
+/// assert_eq!(
+///     json5::to_string_with(&value, &tabs)?,
+///     "{\n\tfoo: [\n\t\t1,\n\t\t2,\n\t],\n}",
+/// );
+/// # Ok::<(), json5::Error>(())
+/// ```
+///
+/// # Errors
+/// Fails if we can't express `T` in JSON5 (e.g. we try to serialize an object key without an
+/// obvious string representation).
+pub fn to_string_with<T: ?Sized + Serialize>(
+    value: &T,
+    config: &SerializerConfig,
+) -> Result<String> {
+    let mut w = Vec::new();
+    to_writer_with(&mut w, value, config)?;
+    #[expect(clippy::missing_panics_doc)]
+    Ok(String::from_utf8(w).expect("we only write valid UTF-8"))
+}
+
+/// Serialize a type implementing [`Serialize`] to JSON5, formatted according to the given config,
+/// and write it to the given writer.
+///
+/// # Errors
+/// Fails if we can't express `T` in JSON5 (e.g. we try to serialize an object key without an
+/// obvious string representation) or if there's an error writing to the writer.
+pub fn to_writer_with<T: ?Sized + Serialize, W: Write>(
+    w: W,
+    value: &T,
+    config: &SerializerConfig,
+) -> Result<()> {
+    value.serialize(&mut Serializer::new(w).with_config(*config))
+}
+
 /// A serializer that knows how to serialize types implementing [`Serialize`] as JSON5.
 pub struct Serializer<W: Write> {
-    w: W,
+    w: Output<W>,
     depth: usize,
+    config: SerializerConfig,
+    // Whether we're inside a `Hex`.
+    hex: bool,
+    // The arrays and objects we're in the middle of when laying them out to fit `max_width`.
+    groups: Vec<Group>,
+}
+
+/// Options controlling how a [`Serializer`] formats its output.
+#[derive(Debug, Clone, Copy)]
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "these are independent options"
+)]
+pub struct SerializerConfig {
+    /// The syntax to write. Defaults to [`Dialect::Json5`].
+    ///
+    /// [`Dialect::Json`] always puts double quotes around strings and object keys, escapes control
+    /// characters in strings, and never writes trailing commas, whatever `quote`, `quote_keys` and
+    /// `trailing_commas` say.
+    pub dialect: Dialect,
+    /// How to write NaN and infinite floats, which plain JSON can't express, when writing
+    /// [`Dialect::Json`]. Defaults to [`NonFinite::Error`].
+    pub non_finite: NonFinite,
+    /// The indentation for each level of nesting. Defaults to two spaces.
+    pub indent: Indent,
+    /// The line ending to put between elements of arrays and objects. Defaults to
+    /// [`LineEnding::Lf`].
+    pub line_ending: LineEnding,
+    /// Write everything on one line, without any whitespace, e.g. `{foo:1,bar:[1,2]}`. `indent`,
+    /// `line_ending` and `max_width` are ignored. Defaults to `false`.
+    pub compact: bool,
+    /// Keep arrays and objects on a single line, e.g. `[1, 2, 3]` or `{ x: 1, y: 2 }`, if they fit
+    /// within this many columns, and only break them across lines if they don't. A tab counts as
+    /// four columns. Defaults to `None`, which always breaks arrays and objects across lines.
+    pub max_width: Option<usize>,
+    /// When to put a comma after the last element of an array or entry of an object. Defaults to
+    /// [`TrailingCommas::Multiline`].
+    pub trailing_commas: TrailingCommas,
+    /// Which quote to put around strings. The other quote is used instead for strings which contain
+    /// the preferred quote but not the other, to avoid escaping. Defaults to [`Quote::Double`].
+    pub quote: Quote,
+    /// Quote object keys even when they're valid identifiers, e.g. `{"foo": 1}` instead of
+    /// `{foo: 1}`. Defaults to `false`.
+    pub quote_keys: bool,
+    /// Escape every non-ASCII character in strings as `\uXXXX` (using a surrogate pair outside the
+    /// Basic Multilingual Plane), along with any control characters, so the output is plain ASCII.
+    /// Object keys which would need escaping are quoted, and comments are escaped the same way as
+    /// strings. Defaults to `false`.
+    pub ascii_only: bool,
+    /// Sort the entries of maps by key using the given comparison, e.g. `Some(str::cmp)` or
+    /// `Some(json5::natural_order)`, so that output doesn't depend on the iteration order of e.g. a
+    /// `HashMap`. Keys are compared as strings, after unescaping. Defaults to `None`, which writes
+    /// entries in the order they're serialized.
+    pub sort_keys: Option<fn(&str, &str) -> Ordering>,
+    /// Sort the fields of structs by `sort_keys` too, rather than keeping them in declaration
+    /// order. Defaults to `false`.
+    pub sort_struct_fields: bool,
+    /// How to write integers. Defaults to [`IntegerFormat::Decimal`]. Ignored when writing
+    /// [`Dialect::Json`], which only has decimal numbers. Integer object keys are always decimal.
+    pub integers: IntegerFormat,
+    /// How to write finite floats. Defaults to [`FloatFormat::Shortest`].
+    pub floats: FloatFormat,
+    /// Write floats with a decimal point even when they're whole numbers, e.g. `1.0` instead of
+    /// `1`, so that they're read back as floats rather than integers (by [`Value`](crate::Value),
+    /// for instance). Defaults to `false`.
+    pub float_point: bool,
+    /// How to write byte arrays. Defaults to [`ByteEncoding::Hex`]. The [`Deserializer`] reads
+    /// hex unless told otherwise by [`ParseOptions::bytes`].
+    ///
+    /// [`Deserializer`]: crate::Deserializer
+    /// [`ParseOptions::bytes`]: crate::ParseOptions::bytes
+    pub bytes: ByteEncoding,
+}
+
+impl Default for SerializerConfig {
+    fn default() -> Self {
+        Self {
+            dialect: Dialect::default(),
+            non_finite: NonFinite::default(),
+            indent: Indent::Spaces(2),
+            line_ending: LineEnding::default(),
+            compact: false,
+            max_width: None,
+            trailing_commas: TrailingCommas::default(),
+            quote: Quote::default(),
+            quote_keys: false,
+            ascii_only: false,
+            sort_keys: None,
+            sort_struct_fields: false,
+            integers: IntegerFormat::default(),
+            floats: FloatFormat::default(),
+            float_point: false,
+            bytes: ByteEncoding::default(),
+        }
+    }
+}
+
+impl SerializerConfig {
+    fn is_json(&self) -> bool {
+        self.dialect == Dialect::Json
+    }
+
+    fn quote_keys(&self) -> bool {
+        self.quote_keys || self.is_json()
+    }
+
+    fn trailing_comma(&self, multiline: bool) -> bool {
+        if self.is_json() {
+            return false;
+        }
+        match self.trailing_commas {
+            TrailingCommas::Always => true,
+            TrailingCommas::Never => false,
+            TrailingCommas::Multiline => multiline,
+        }
+    }
+
+    // The quote to put around the given string.
+    fn delimiter(&self, s: &str) -> char {
+        if self.is_json() {
+            return '"';
+        }
+        let (preferred, other) = match self.quote {
+            Quote::Double => ('"', '\''),
+            Quote::Single => ('\'', '"'),
+        };
+        if s.contains(preferred) && !s.contains(other) {
+            other
+        } else {
+            preferred
+        }
+    }
+
+    fn indent_width(&self) -> usize {
+        match self.indent {
+            Indent::Spaces(n) => n,
+            Indent::Tabs => 4,
+        }
+    }
+
+    // Starts a new line, indented to the given depth, and returns the new column.
+    fn write_newline(&self, w: &mut impl Write, depth: usize) -> Result<usize> {
+        write!(w, "{}", self.line_ending.as_str())?;
+        match self.indent {
+            Indent::Spaces(n) => write!(w, "{:indent$}", "", indent = depth * n)?,
+            Indent::Tabs => write!(w, "{:\t<indent$}", "", indent = depth)?,
+        }
+        Ok(depth * self.indent_width())
+    }
+}
+
+/// How to indent nested arrays and objects.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Indent {
+    /// The given number of spaces for each level of nesting.
+    Spaces(usize),
+    /// A tab for each level of nesting.
+    Tabs,
+}
+
+/// Which line ending to use.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum LineEnding {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+/// Compares strings in "natural" order, where runs of ASCII digits are compared by their numeric
+/// value, so `"item2"` comes before `"item10"`. For use with [`SerializerConfig::sort_keys`].
+///
+/// # Example
+/// ```
+/// let mut keys = ["item10", "item2", "Item1", "item1"];
+/// keys.sort_by(|a, b| json5::natural_order(a, b));
+/// assert_eq!(keys, ["Item1", "item1", "item2", "item10"]);
+/// ```
+#[must_use]
+pub fn natural_order(mut a: &str, mut b: &str) -> Ordering {
+    fn split_digits(s: &str) -> (&str, &str) {
+        s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()))
+    }
+
+    loop {
+        let (Some(x), Some(y)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let (digits_a, rest_a) = split_digits(a);
+            let (digits_b, rest_b) = split_digits(b);
+            let (trimmed_a, trimmed_b) = (
+                digits_a.trim_start_matches('0'),
+                digits_b.trim_start_matches('0'),
+            );
+            let ordering = (trimmed_a.len().cmp(&trimmed_b.len()))
+                .then_with(|| trimmed_a.cmp(trimmed_b))
+                .then_with(|| digits_a.len().cmp(&digits_b.len()));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            (a, b) = (rest_a, rest_b);
+        } else {
+            if x != y {
+                return x.cmp(&y);
+            }
+            (a, b) = (&a[x.len_utf8()..], &b[y.len_utf8()..]);
+        }
+    }
+}
+
+/// How to write NaN and infinite floats in plain JSON.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum NonFinite {
+    /// Fail with [`ErrorCode::NanInJson`] or [`ErrorCode::InfinityInJson`].
+    #[default]
+    Error,
+    /// Write `null`, like `JSON.stringify`.
+    Null,
+    /// Write the JSON5 literal as a string, i.e. `"NaN"`, `"Infinity"` or `"-Infinity"`.
+    String,
+}
+
+/// When to put a comma after the last element of an array or entry of an object.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum TrailingCommas {
+    /// Always, e.g. `[1, 2,]`.
+    Always,
+    /// Never, e.g. `[1, 2]`.
+    Never,
+    /// Only when the array or object is broken across lines.
+    #[default]
+    Multiline,
+}
+
+/// How to write integers.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum IntegerFormat {
+    /// e.g. `3735928559`
+    #[default]
+    Decimal,
+    /// Hexadecimal with lower case digits, e.g. `0xdeadbeef`.
+    LowerHex,
+    /// Hexadecimal with upper case digits, e.g. `0xDEADBEEF`.
+    UpperHex,
+}
+
+/// How to write finite floats.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum FloatFormat {
+    /// The fewest digits that read back as the same float, without an exponent, e.g. `0.1` or
+    /// `1000000`.
+    #[default]
+    Shortest,
+    /// Exactly this many digits after the decimal point, rounding if need be, e.g. `0.10` for
+    /// `Fixed(2)`. Digits beyond the precision of the float may not read back exactly.
+    Fixed(usize),
+}
+
+/// Which quote to put around strings.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Quote {
+    /// `"`
+    #[default]
+    Double,
+    /// `'`
+    Single,
+}
+
+/// The name of the newtype struct that [`Hex`] serializes as, which tells our [`Serializer`] to
+/// write integers in hexadecimal.
+const HEX: &str = "$json5::private::Hex";
+
+/// An integer, or anything containing integers, which is written in hexadecimal when serializing
+/// as JSON5, e.g. `0xDEADBEEF`. Negative integers are written as e.g. `-0x1F`.
+///
+/// The digits are upper case, unless [`SerializerConfig::integers`] asks for lower case. Plain JSON
+/// has no hexadecimal numbers, so it gets decimal. Other serializers see just the value, and
+/// deserializing reads the value whichever way it's written.
+///
+/// # Example
+/// ```
+/// use json5::ser::Hex;
+/// use serde_derive::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Register {
+///     address: Hex<u32>,
+///     mask: Hex<[u8; 2]>,
+///     reset: u32,
+/// }
+///
+/// let register = Register {
+///     address: Hex(0xdeadbeef),
+///     mask: Hex([0x0f, 0xf0]),
+///     reset: 0,
+/// };
+///
+/// assert_eq!(json5::to_string(&register)?, "{
+///   address: 0xDEADBEEF,
+///   mask: [
+///     0xF,
+///     0xF0,
+///   ],
+///   reset: 0,
+/// }");
+/// # Ok::<(), json5::Error>(())
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Hex<T>(pub T);
+
+impl<T: Serialize> Serialize for Hex<T> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(HEX, &self.0)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Hex<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Hex)
+    }
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+        }
+    }
 }
 
 impl<W: Write> Serializer<W> {
     pub fn new(w: W) -> Self {
-        Self { w, depth: 0 }
+        Self {
+            w: Output {
+                w,
+                buffer: None,
+                key: Vec::new(),
+                holding_key: false,
+            },
+            depth: 0,
+            config: SerializerConfig::default(),
+            hex: false,
+            groups: Vec::new(),
+        }
+    }
+
+    /// Format output according to the given config.
+    #[must_use]
+    pub fn with_config(mut self, config: SerializerConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    // Writes bytes as a string, in hex if the config asks for an array.
+    fn write_bytes_string(&mut self, v: &[u8]) -> Result<()> {
+        let delimiter = self.config.delimiter("");
+        write!(self.w, "{delimiter}")?;
+        bytes::encode(&mut self.w, v, self.config.bytes)?;
+        write!(self.w, "{delimiter}")?;
+        Ok(())
+    }
+
+    fn write_integer(&mut self, negative: bool, magnitude: u128) -> Result<()> {
+        let sign = if negative { "-" } else { "" };
+        let format = match self.config.integers {
+            _ if self.config.is_json() => IntegerFormat::Decimal,
+            IntegerFormat::Decimal if self.hex => IntegerFormat::UpperHex,
+            format => format,
+        };
+        match format {
+            IntegerFormat::Decimal => write!(self.w, "{sign}{magnitude}")?,
+            IntegerFormat::LowerHex => write!(self.w, "{sign}{magnitude:#x}")?,
+            IntegerFormat::UpperHex => write!(self.w, "{sign}{magnitude:#X}")?,
+        }
+        Ok(())
+    }
+
+    // The text to write for a NaN or infinite float, and whether it needs quoting.
+    fn non_finite(&self, v: f64) -> Result<(&'static str, bool)> {
+        let literal = match (v.is_nan(), v.is_sign_negative()) {
+            (true, false) => "NaN",
+            (true, true) => "-NaN",
+            (false, false) => "Infinity",
+            (false, true) => "-Infinity",
+        };
+        if !self.config.is_json() {
+            return Ok((literal, false));
+        }
+        match self.config.non_finite {
+            NonFinite::Error if v.is_nan() => Err(Error::new(ErrorCode::NanInJson)),
+            NonFinite::Error => Err(Error::new(ErrorCode::InfinityInJson)),
+            NonFinite::Null => Ok(("null", false)),
+            NonFinite::String if v.is_nan() => Ok(("NaN", true)),
+            NonFinite::String => Ok((literal, true)),
+        }
+    }
+
+    // Whether we need to see each array or object in full before writing it, to lay it out to fit
+    // `max_width` or to sort its keys.
+    fn buffered(&self) -> bool {
+        (self.config.max_width.is_some() && !self.config.compact) || self.config.sort_keys.is_some()
+    }
+
+    // Starts a new line, indented to the current depth.
+    fn write_newline(&mut self) -> Result<()> {
+        if !self.config.compact {
+            self.config.write_newline(&mut self.w, self.depth)?;
+        }
+        Ok(())
+    }
+
+    fn write_colon(&mut self) -> Result<()> {
+        if self.config.compact {
+            write!(self.w, ":")?;
+        } else {
+            write!(self.w, ": ")?;
+        }
+        Ok(())
+    }
+
+    // Opens an array or an object. When buffered, we decide how to lay it out, and which order to
+    // put its entries in if `sort` is set, once it's closed.
+    fn begin_collection(&mut self, delimiter: char, sort: bool) -> Result<()> {
+        if self.buffered() {
+            if self.groups.is_empty() {
+                self.w.buffer = Some(Vec::new());
+            } else {
+                self.flush_buffer();
+            }
+            self.groups.push(Group {
+                open: delimiter,
+                entries: Vec::new(),
+                width: 0,
+                sort,
+            });
+        } else {
+            write!(self.w, "{delimiter}")?;
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    // Writes everything before an element of an array or an entry of an object.
+    fn begin_entry(&mut self, first: bool) -> Result<()> {
+        if self.buffered() {
+            self.flush_buffer();
+            if let Some(group) = self.groups.last_mut() {
+                group.entries.push(Vec::new());
+            }
+            return Ok(());
+        }
+        if !first {
+            write!(self.w, ",")?;
+        }
+        self.write_newline()
+    }
+
+    fn end_collection(&mut self, empty: bool, delimiter: char) -> Result<()> {
+        self.depth -= 1;
+        if self.buffered() {
+            self.flush_buffer();
+            let mut group = self
+                .groups
+                .pop()
+                .expect("we opened a group for every collection");
+            if let Some(compare) = self.config.sort_keys.filter(|_| group.sort) {
+                group.sort_entries(compare);
+            }
+            group.measure(&self.config);
+            if let Some(parent) = self.groups.last_mut() {
+                if let Some(entry) = parent.entries.last_mut() {
+                    entry.push(Piece::Group(group));
+                }
+            } else {
+                self.w.buffer = None;
+                if self.config.compact {
+                    group.write_inline(&mut self.w, &self.config)?;
+                } else {
+                    let mut layout = Layout {
+                        w: &mut self.w,
+                        config: &self.config,
+                        max_width: self.config.max_width.unwrap_or(0),
+                        column: 0,
+                    };
+                    layout.group(&group, self.depth, 0)?;
+                }
+            }
+            return Ok(());
+        }
+        if !empty {
+            if self.config.trailing_comma(!self.config.compact) {
+                write!(self.w, ",")?;
+            }
+            self.write_newline()?;
+        }
+        write!(self.w, "{delimiter}")?;
+        Ok(())
+    }
+
+    // Writes a comment on the lines before the current value, and before its key if it's in an
+    // object.
+    fn write_comment(&mut self, comment: &str) -> Result<()> {
+        if comment.is_empty() || self.config.compact || self.config.is_json() {
+            return Ok(());
+        }
+        if self.buffered() && !self.groups.is_empty() {
+            self.flush_buffer();
+            if let Some(entry) = self
+                .groups
+                .last_mut()
+                .and_then(|group| group.entries.last_mut())
+            {
+                entry.insert(0, Piece::Comment(comment.to_owned()));
+            }
+            return Ok(());
+        }
+        let key = std::mem::take(&mut self.w.key);
+        for line in crate::char::lines(comment) {
+            write_comment_line(&mut self.w, line, self.config.ascii_only)?;
+            self.config.write_newline(&mut self.w, self.depth)?;
+        }
+        self.w.write_all(&key)?;
+        Ok(())
+    }
+
+    // Moves anything written since the last array or object delimiter into the current entry.
+    fn flush_buffer(&mut self) {
+        let Some(buffer) = self.w.buffer.as_mut().filter(|buffer| !buffer.is_empty()) else {
+            return;
+        };
+        let text = String::from_utf8(std::mem::take(buffer)).expect("we only write valid UTF-8");
+        if let Some(entry) = self
+            .groups
+            .last_mut()
+            .and_then(|group| group.entries.last_mut())
+        {
+            entry.push(Piece::Text(text));
+        }
+    }
+
+    // Writes the opening of an enum variant with data, as `{ variant: ...`.
+    fn begin_variant(&mut self, variant: &'static str) -> Result<()> {
+        self.begin_collection('{', false)?;
+        self.begin_entry(true)?;
+        self.w.holding_key = true;
+        serde::Serializer::serialize_str(MapKey::new(self), variant)?;
+        self.write_colon()?;
+        self.w.holding_key = false;
+        Ok(())
+    }
+
+    fn end_variant(&mut self) -> Result<()> {
+        self.end_collection(false, '}')
+    }
+}
+
+// The writer a `Serializer` writes to, which can instead buffer output while we work out how to lay
+// out an array or an object.
+struct Output<W> {
+    w: W,
+    buffer: Option<Vec<u8>>,
+    // When not buffering, we hold back each object key until its value is written, in case the
+    // value has a comment to go before it.
+    key: Vec<u8>,
+    holding_key: bool,
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(buffer) = &mut self.buffer {
+            return buffer.write(buf);
+        }
+        if self.holding_key {
+            return self.key.write(buf);
+        }
+        if !self.key.is_empty() {
+            self.w.write_all(&self.key)?;
+            self.key.clear();
+        }
+        self.w.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+// A buffered array or object, which is either laid out on one line or with an entry per line.
+struct Group {
+    open: char,
+    entries: Vec<Vec<Piece>>,
+    // The width when laid out on one line.
+    width: usize,
+    // Whether to sort the entries by key.
+    sort: bool,
+}
+
+enum Piece {
+    Text(String),
+    Group(Group),
+    // Always comes first in an entry, and never fits on one line.
+    Comment(String),
+}
+
+impl Piece {
+    fn width(&self) -> usize {
+        match self {
+            Piece::Text(text) => text.chars().count(),
+            Piece::Group(group) => group.width,
+            Piece::Comment(_) => usize::MAX,
+        }
+    }
+}
+
+impl Group {
+    fn is_object(&self) -> bool {
+        self.open == '{'
+    }
+
+    fn close(&self) -> char {
+        if self.is_object() { '}' } else { ']' }
+    }
+
+    // Sorts the entries of an object by key. The key is the first piece of each entry, after any
+    // comment.
+    fn sort_entries(&mut self, compare: fn(&str, &str) -> Ordering) {
+        let mut entries: Vec<_> = std::mem::take(&mut self.entries)
+            .into_iter()
+            .map(|entry| {
+                let key = match entry
+                    .iter()
+                    .find(|piece| !matches!(piece, Piece::Comment(_)))
+                {
+                    Some(Piece::Text(text)) if text.starts_with(['"', '\'']) => {
+                        crate::from_str(text).unwrap_or_else(|_| text.clone())
+                    }
+                    Some(Piece::Text(text)) => text.clone(),
+                    _ => String::new(),
+                };
+                (key, entry)
+            })
+            .collect();
+        entries.sort_by(|(a, _), (b, _)| compare(a, b));
+        self.entries = entries.into_iter().map(|(_, entry)| entry).collect();
+    }
+
+    // The separator between entries, and the padding inside braces, when laid out on one line.
+    fn inline_spacing(&self, config: &SerializerConfig) -> (&'static str, &'static str) {
+        match (config.compact, self.is_object() && !self.entries.is_empty()) {
+            (true, _) => (",", ""),
+            (false, true) => (", ", " "),
+            (false, false) => (", ", ""),
+        }
+    }
+
+    fn measure(&mut self, config: &SerializerConfig) {
+        let (separator, padding) = self.inline_spacing(config);
+        let entries =
+            (self.entries.iter().flatten().map(Piece::width)).fold(0, usize::saturating_add);
+        let separators = separator.len() * self.entries.len().saturating_sub(1);
+        let trailing_comma = usize::from(config.trailing_comma(false) && !self.entries.is_empty());
+        self.width = entries.saturating_add(2 + separators + trailing_comma + 2 * padding.len());
+    }
+
+    fn write_inline(&self, w: &mut impl Write, config: &SerializerConfig) -> Result<()> {
+        let (separator, padding) = self.inline_spacing(config);
+        write!(w, "{}{padding}", self.open)?;
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                write!(w, "{separator}")?;
+            }
+            for piece in entry {
+                match piece {
+                    Piece::Text(text) => write!(w, "{text}")?,
+                    Piece::Group(group) => group.write_inline(w, config)?,
+                    Piece::Comment(_) => {}
+                }
+            }
+        }
+        if config.trailing_comma(false) && !self.entries.is_empty() {
+            write!(w, ",")?;
+        }
+        write!(w, "{padding}{}", self.close())?;
+        Ok(())
     }
 }
 
-macro_rules! serialize_display {
+struct Layout<'a, W> {
+    w: &'a mut W,
+    config: &'a SerializerConfig,
+    max_width: usize,
+    column: usize,
+}
+
+impl<W: Write> Layout<'_, W> {
+    // Writes a group starting at the current column at the given depth, followed by `suffix`
+    // columns of text on the same line.
+    fn group(&mut self, group: &Group, depth: usize, suffix: usize) -> Result<()> {
+        let width = self
+            .column
+            .saturating_add(group.width)
+            .saturating_add(suffix);
+        if group.entries.is_empty() || width <= self.max_width {
+            group.write_inline(self.w, self.config)?;
+            self.column += group.width;
+            return Ok(());
+        }
+        write!(self.w, "{}", group.open)?;
+        for (i, entry) in group.entries.iter().enumerate() {
+            let comma = i + 1 < group.entries.len() || self.config.trailing_comma(true);
+            self.column = self.config.write_newline(self.w, depth + 1)?;
+            for (j, piece) in entry.iter().enumerate() {
+                match piece {
+                    Piece::Text(text) => {
+                        write!(self.w, "{text}")?;
+                        self.column += piece.width();
+                    }
+                    Piece::Group(inner) => {
+                        let rest: usize = entry[j + 1..].iter().map(Piece::width).sum();
+                        self.group(inner, depth + 1, rest + usize::from(comma))?;
+                    }
+                    Piece::Comment(comment) => {
+                        for line in crate::char::lines(comment) {
+                            write_comment_line(self.w, line, self.config.ascii_only)?;
+                            self.column = self.config.write_newline(self.w, depth + 1)?;
+                        }
+                    }
+                }
+            }
+            if comma {
+                write!(self.w, ",")?;
+            }
+        }
+        self.config.write_newline(self.w, depth)?;
+        write!(self.w, "{}", group.close())?;
+        self.column = depth * self.config.indent_width() + 1;
+        Ok(())
+    }
+}
+
+/// Writes one line of a comment, without trailing whitespace if the line is blank. With
+/// `ascii_only`, non-ASCII characters are written as `\uXXXX` like they are in strings, since
+/// comments have no escapes of their own.
+fn write_comment_line(w: &mut impl Write, line: &str, ascii_only: bool) -> io::Result<()> {
+    if line.is_empty() {
+        return write!(w, "//");
+    }
+    write!(w, "// ")?;
+    if !ascii_only {
+        return write!(w, "{line}");
+    }
+    for c in line.chars() {
+        if c.is_ascii() {
+            write!(w, "{c}")?;
+        } else {
+            for unit in c.encode_utf16(&mut [0; 2]) {
+                write!(w, r"\u{unit:04x}")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+macro_rules! serialize_unsigned {
+    ($method:ident, $type:ty) => {
+        fn $method(self, v: $type) -> Result<Self::Ok> {
+            self.write_integer(false, v.into())
+        }
+    };
+}
+
+macro_rules! serialize_signed {
     ($method:ident, $type:ty) => {
         fn $method(self, v: $type) -> Result<Self::Ok> {
-            write!(self.w, "{v}").map_err(Into::into)
+            self.write_integer(v < 0, v.unsigned_abs().into())
         }
     };
 }
@@ -70,14 +900,27 @@ macro_rules! serialize_display {
 macro_rules! serialize_float {
     ($method:ident, $type:ty) => {
         fn $method(self, v: $type) -> Result<Self::Ok> {
-            match (v.is_nan(), v.is_infinite(), v.is_sign_negative()) {
-                (true, false, false) => write!(self.w, "NaN"),
-                (true, false, true) => write!(self.w, "-NaN"),
-                (false, true, false) => write!(self.w, "Infinity"),
-                (false, true, true) => write!(self.w, "-Infinity"),
-                _ => write!(self.w, "{v}"),
+            if v.is_finite() {
+                let point = match self.config.floats {
+                    FloatFormat::Shortest => {
+                        write!(self.w, "{v}")?;
+                        v.fract() != 0.0
+                    }
+                    FloatFormat::Fixed(precision) => {
+                        write!(self.w, "{v:.precision$}")?;
+                        precision > 0
+                    }
+                };
+                if self.config.float_point && !point {
+                    write!(self.w, ".0")?;
+                }
+            } else {
+                match self.non_finite(f64::from(v))? {
+                    (text, true) => self.serialize_str(text)?,
+                    (text, false) => write!(self.w, "{text}")?,
+                }
             }
-            .map_err(Into::into)
+            Ok(())
         }
     };
 }
@@ -93,41 +936,48 @@ impl<'a, W: Write> serde::ser::Serializer for &'a mut Serializer<W> {
     type SerializeStruct = SerializeCollection<'a, W>;
     type SerializeStructVariant = SerializeCollection<'a, W>;
 
-    serialize_display!(serialize_bool, bool);
-    serialize_display!(serialize_u8, u8);
-    serialize_display!(serialize_u16, u16);
-    serialize_display!(serialize_u32, u32);
-    serialize_display!(serialize_u64, u64);
-    serialize_display!(serialize_u128, u128);
-    serialize_display!(serialize_i8, i8);
-    serialize_display!(serialize_i16, i16);
-    serialize_display!(serialize_i32, i32);
-    serialize_display!(serialize_i64, i64);
-    serialize_display!(serialize_i128, i128);
+    serialize_unsigned!(serialize_u8, u8);
+    serialize_unsigned!(serialize_u16, u16);
+    serialize_unsigned!(serialize_u32, u32);
+    serialize_unsigned!(serialize_u64, u64);
+    serialize_unsigned!(serialize_u128, u128);
+    serialize_signed!(serialize_i8, i8);
+    serialize_signed!(serialize_i16, i16);
+    serialize_signed!(serialize_i32, i32);
+    serialize_signed!(serialize_i64, i64);
+    serialize_signed!(serialize_i128, i128);
     serialize_float!(serialize_f32, f32);
     serialize_float!(serialize_f64, f64);
 
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
+        write!(self.w, "{v}").map_err(Into::into)
+    }
+
     fn serialize_char(self, v: char) -> Result<Self::Ok> {
-        if v == '"' {
-            write!(self.w, r#"'"'"#)
-        } else if let Some(escaped) = crate::char::escape('"', v) {
-            write!(self.w, r#""{escaped}""#)
-        } else {
-            write!(self.w, r#""{v}""#)
-        }
-        .map_err(Into::into)
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok> {
-        let delimiter = if v.contains('"') && !v.contains('\'') {
-            '\''
-        } else {
-            '"'
-        };
+        let delimiter = self.config.delimiter(v);
         write!(self.w, "{delimiter}")?;
         for c in v.chars() {
             match crate::char::escape(delimiter, c) {
                 Some(escaped) => write!(self.w, "{escaped}")?,
+                None if c.is_ascii_control()
+                    && (self.config.is_json() || self.config.ascii_only) =>
+                {
+                    match c {
+                        '\t' => write!(self.w, r"\t")?,
+                        '\u{8}' => write!(self.w, r"\b")?,
+                        '\u{c}' => write!(self.w, r"\f")?,
+                        _ => write!(self.w, r"\u{:04x}", u32::from(c))?,
+                    }
+                }
+                None if !c.is_ascii() && self.config.ascii_only => {
+                    for unit in c.encode_utf16(&mut [0; 2]) {
+                        write!(self.w, r"\u{unit:04x}")?;
+                    }
+                }
                 None => write!(self.w, "{c}")?,
             }
         }
@@ -136,12 +986,10 @@ impl<'a, W: Write> serde::ser::Serializer for &'a mut Serializer<W> {
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
-        write!(self.w, "\"")?;
-        for b in v {
-            write!(self.w, "{b:02x}")?;
+        if self.config.bytes == ByteEncoding::Array {
+            return serde::Serializer::collect_seq(self, v);
         }
-        write!(self.w, "\"")?;
-        Ok(())
+        self.write_bytes_string(v)
     }
 
     fn serialize_none(self) -> Result<Self::Ok> {
@@ -172,10 +1020,21 @@ impl<'a, W: Write> serde::ser::Serializer for &'a mut Serializer<W> {
         self.serialize_str(variant)
     }
 
-    fn serialize_newtype_struct<T>(self, _: &'static str, v: &T) -> Result<Self::Ok>
+    fn serialize_newtype_struct<T>(self, name: &'static str, v: &T) -> Result<Self::Ok>
     where
         T: ?Sized + Serialize,
     {
+        if name == commented::NAME
+            && let Some(comment) = commented::take_comment()
+        {
+            self.write_comment(&comment)?;
+        }
+        if name == HEX {
+            let hex = std::mem::replace(&mut self.hex, true);
+            let result = v.serialize(&mut *self);
+            self.hex = hex;
+            return result;
+        }
         v.serialize(self)
     }
 
@@ -189,20 +1048,13 @@ impl<'a, W: Write> serde::ser::Serializer for &'a mut Serializer<W> {
     where
         T: ?Sized + Serialize,
     {
-        write!(self.w, "{{")?;
-        self.depth += 1;
-        write!(self.w, "\n{:indent$}", "", indent = self.depth * 2)?;
-        MapKey::new(self).serialize_str(variant)?;
-        write!(self.w, ": ")?;
+        self.begin_variant(variant)?;
         v.serialize(&mut *self)?;
-        self.depth -= 1;
-        write!(self.w, ",\n{:indent$}}}", "", indent = self.depth * 2)?;
-        Ok(())
+        self.end_variant()
     }
 
     fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq> {
-        write!(self.w, "[")?;
-        self.depth += 1;
+        self.begin_collection('[', false)?;
         Ok(SerializeCollection::new(self))
     }
 
@@ -225,22 +1077,18 @@ impl<'a, W: Write> serde::ser::Serializer for &'a mut Serializer<W> {
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        write!(self.w, "{{")?;
-        self.depth += 1;
-        write!(self.w, "\n{:indent$}", "", indent = self.depth * 2)?;
-        MapKey::new(self).serialize_str(variant)?;
-        write!(self.w, ": ")?;
+        self.begin_variant(variant)?;
         self.serialize_seq(Some(len))
     }
 
     fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap> {
-        write!(self.w, "{{")?;
-        self.depth += 1;
+        self.begin_collection('{', true)?;
         Ok(SerializeCollection::new(self))
     }
 
-    fn serialize_struct(self, _: &'static str, len: usize) -> Result<Self::SerializeStruct> {
-        self.serialize_map(Some(len))
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct> {
+        self.begin_collection('{', self.config.sort_struct_fields)?;
+        Ok(SerializeCollection::new(self))
     }
 
     fn serialize_struct_variant(
@@ -250,12 +1098,8 @@ impl<'a, W: Write> serde::ser::Serializer for &'a mut Serializer<W> {
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        write!(self.w, "{{")?;
-        self.depth += 1;
-        write!(self.w, "\n{:indent$}", "", indent = self.depth * 2)?;
-        MapKey::new(self).serialize_str(variant)?;
-        write!(self.w, ": ")?;
-        self.serialize_map(Some(len))
+        self.begin_variant(variant)?;
+        self.serialize_struct(variant, len)
     }
 }
 
@@ -269,20 +1113,15 @@ impl<'a, W: Write> SerializeCollection<'a, W> {
         Self { ser, empty: true }
     }
 
-    fn close(&mut self, delimiter: char) -> Result<()> {
-        self.ser.depth -= 1;
-        if self.empty {
-            write!(self.ser.w, "{delimiter}")?;
-        } else {
-            write!(
-                self.ser.w,
-                "\n{:indent$}{delimiter}",
-                "",
-                indent = self.ser.depth * 2
-            )?;
-        }
+    fn begin_entry(&mut self) -> Result<()> {
+        self.ser.begin_entry(self.empty)?;
+        self.empty = false;
         Ok(())
     }
+
+    fn close(&mut self, delimiter: char) -> Result<()> {
+        self.ser.end_collection(self.empty, delimiter)
+    }
 }
 
 impl<W: Write> serde::ser::SerializeSeq for SerializeCollection<'_, W> {
@@ -293,11 +1132,8 @@ impl<W: Write> serde::ser::SerializeSeq for SerializeCollection<'_, W> {
     where
         T: ?Sized + Serialize,
     {
-        self.empty = false;
-        write!(self.ser.w, "\n{:indent$}", "", indent = self.ser.depth * 2)?;
-        value.serialize(&mut *self.ser)?;
-        write!(self.ser.w, ",")?;
-        Ok(())
+        self.begin_entry()?;
+        value.serialize(&mut *self.ser)
     }
 
     fn end(mut self) -> Result<Self::Ok> {
@@ -350,14 +1186,7 @@ impl<W: Write> serde::ser::SerializeTupleVariant for SerializeCollection<'_, W>
 
     fn end(mut self) -> Result<Self::Ok> {
         self.close(']')?;
-        self.ser.depth -= 1;
-        write!(
-            self.ser.w,
-            ",\n{:indent$}}}",
-            "",
-            indent = self.ser.depth * 2
-        )?;
-        Ok(())
+        self.ser.end_variant()
     }
 }
 
@@ -369,10 +1198,13 @@ impl<W: Write> serde::ser::SerializeMap for SerializeCollection<'_, W> {
     where
         T: ?Sized + Serialize,
     {
-        self.empty = false;
-        write!(self.ser.w, "\n{:indent$}", "", indent = self.ser.depth * 2)?;
+        self.begin_entry()?;
+        self.ser.w.holding_key = true;
         key.serialize(MapKey::new(self.ser))?;
-        write!(self.ser.w, ": ")?;
+        // Keep the key in a piece of its own, so we can sort by it.
+        self.ser.flush_buffer();
+        self.ser.write_colon()?;
+        self.ser.w.holding_key = false;
         Ok(())
     }
 
@@ -380,9 +1212,7 @@ impl<W: Write> serde::ser::SerializeMap for SerializeCollection<'_, W> {
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut *self.ser)?;
-        write!(self.ser.w, ",")?;
-        Ok(())
+        value.serialize(&mut *self.ser)
     }
 
     fn end(mut self) -> Result<Self::Ok> {
@@ -419,28 +1249,35 @@ impl<W: Write> serde::ser::SerializeStructVariant for SerializeCollection<'_, W>
 
     fn end(mut self) -> Result<Self::Ok> {
         self.close('}')?;
-        self.ser.depth -= 1;
-        write!(
-            self.ser.w,
-            ",\n{:indent$}}}",
-            "",
-            indent = self.ser.depth * 2
-        )?;
-        Ok(())
+        self.ser.end_variant()
     }
 }
 
 macro_rules! serialize_quoted {
     ($method:ident, $type:ty) => {
         fn $method(self, v: $type) -> Result<Self::Ok> {
-            write!(self.ser.w, "\"")?;
-            self.ser.$method(v)?;
-            write!(self.ser.w, "\"")?;
+            let delimiter = self.ser.config.delimiter("");
+            write!(self.ser.w, "{delimiter}{v}{delimiter}")?;
             Ok(())
         }
     };
 }
 
+macro_rules! serialize_quoted_float {
+    ($method:ident, $type:ty) => {
+        fn $method(self, v: $type) -> Result<Self::Ok> {
+            if v.is_finite() {
+                let delimiter = self.ser.config.delimiter("");
+                write!(self.ser.w, "{delimiter}{v}{delimiter}")?;
+                Ok(())
+            } else {
+                let (text, _) = self.ser.non_finite(f64::from(v))?;
+                self.ser.serialize_str(text)
+            }
+        }
+    };
+}
+
 struct MapKey<'a, W: Write> {
     ser: &'a mut Serializer<W>,
 }
@@ -472,27 +1309,22 @@ impl<W: Write> serde::ser::Serializer for MapKey<'_, W> {
     serialize_quoted!(serialize_i32, i32);
     serialize_quoted!(serialize_i64, i64);
     serialize_quoted!(serialize_i128, i128);
-    serialize_quoted!(serialize_f32, f32);
-    serialize_quoted!(serialize_f64, f64);
+    serialize_quoted_float!(serialize_f32, f32);
+    serialize_quoted_float!(serialize_f64, f64);
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
-        self.ser.serialize_bool(v)
+        self.serialize_str(if v { "true" } else { "false" })
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok> {
-        if crate::char::is_json5_identifier_start(v) {
-            write!(self.ser.w, "{v}")?;
-        } else {
-            self.ser.serialize_char(v)?;
-        }
-        Ok(())
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
     }
 
+    // Every key that isn't a number or bytes ends up here, to be quoted if need be.
     fn serialize_str(self, v: &str) -> Result<Self::Ok> {
-        let mut chars = v.chars();
-        if let Some(first) = chars.next()
-            && crate::char::is_json5_identifier_start(first)
-            && chars.all(crate::char::is_json5_identifier)
+        if !self.ser.config.quote_keys()
+            && (v.is_ascii() || !self.ser.config.ascii_only)
+            && crate::char::is_json5_identifier_name(v)
         {
             write!(self.ser.w, "{v}")?;
         } else {
@@ -511,22 +1343,22 @@ impl<W: Write> serde::ser::Serializer for MapKey<'_, W> {
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
-        self.ser.serialize_bytes(v)
+        self.ser.write_bytes_string(v)
     }
 
     fn serialize_some<T>(self, v: &T) -> Result<Self::Ok>
     where
         T: ?Sized + Serialize,
     {
-        self.ser.serialize_some(v)
+        v.serialize(self)
     }
 
     fn serialize_unit(self) -> Result<Self::Ok> {
-        self.ser.serialize_unit()
+        self.serialize_str("null")
     }
 
-    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok> {
-        self.ser.serialize_unit_struct(name)
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Self::Ok> {
+        self.serialize_unit()
     }
 
     fn serialize_newtype_struct<T>(self, _: &'static str, value: &T) -> Result<Self::Ok>
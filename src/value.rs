@@ -0,0 +1,448 @@
+use std::{
+    fmt::{Display, Formatter},
+    ops::{Index, IndexMut},
+    str::FromStr,
+};
+
+use crate::error::{Error, Result};
+
+mod de;
+mod ser;
+
+pub use de::{from_value, from_value_with};
+pub use ser::{to_value, to_value_with};
+
+/// The map type used to represent JSON5 objects in a [`Value`].
+///
+/// Keys are sorted by default. With the `preserve_order` feature enabled, keys are kept in the
+/// order they were inserted (or parsed) instead, so they round trip unchanged.
+#[cfg(not(feature = "preserve_order"))]
+pub type Map = std::collections::BTreeMap<String, Value>;
+
+/// The map type used to represent JSON5 objects in a [`Value`].
+///
+/// Keys are sorted by default. With the `preserve_order` feature enabled, keys are kept in the
+/// order they were inserted (or parsed) instead, so they round trip unchanged.
+#[cfg(feature = "preserve_order")]
+pub type Map = indexmap::IndexMap<String, Value>;
+
+/// Any valid JSON5 value.
+///
+/// # Example
+/// ```
+/// use json5::Value;
+///
+/// let value: Value = "{ name: 'Joe', ids: [0x10, 1e3, Infinity] }".parse()?;
+///
+/// assert_eq!(value["name"], Value::from("Joe"));
+/// assert_eq!(value["ids"][0].as_u64(), Some(16));
+/// assert_eq!(value["ids"][2].as_f64(), Some(f64::INFINITY));
+/// assert_eq!(value["missing"], Value::Null);
+/// # Ok::<(), json5::Error>(())
+/// ```
+#[derive(Debug, Default, PartialEq, Clone)]
+pub enum Value {
+    #[default]
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(String),
+    Array(Vec<Value>),
+    Object(Map),
+}
+
+impl Value {
+    #[must_use]
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_number(&self) -> Option<&Number> {
+        match self {
+            Value::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number().and_then(Number::as_u64)
+    }
+
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number().and_then(Number::as_i64)
+    }
+
+    #[must_use]
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number().map(Number::as_f64)
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Value>> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_object(&self) -> Option<&Map> {
+        match self {
+            Value::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_object_mut(&mut self) -> Option<&mut Map> {
+        match self {
+            Value::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// Look up a key in an object. Returns `None` if the key is missing or if this isn't an object.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_object().and_then(|o| o.get(key))
+    }
+
+    /// Look up a key in an object. Returns `None` if the key is missing or if this isn't an object.
+    #[must_use]
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.as_object_mut().and_then(|o| o.get_mut(key))
+    }
+}
+
+/// A JSON5 number. Integers are stored exactly, across the full range of [`u128`] and [`i128`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Number(N);
+
+// Invariant: I128 is only used for negative integers, so that each integer has exactly one
+// representation and the derived PartialEq is correct.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum N {
+    U128(u128),
+    I128(i128),
+    F64(f64),
+}
+
+impl Number {
+    #[must_use]
+    pub fn is_integer(&self) -> bool {
+        !matches!(self.0, N::F64(_))
+    }
+
+    #[must_use]
+    pub fn is_f64(&self) -> bool {
+        matches!(self.0, N::F64(_))
+    }
+
+    #[must_use]
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_u128().and_then(|u| u64::try_from(u).ok())
+    }
+
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_i128().and_then(|i| i64::try_from(i).ok())
+    }
+
+    #[must_use]
+    pub fn as_u128(&self) -> Option<u128> {
+        match self.0 {
+            N::U128(u) => Some(u),
+            N::I128(_) | N::F64(_) => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_i128(&self) -> Option<i128> {
+        match self.0 {
+            N::U128(u) => i128::try_from(u).ok(),
+            N::I128(i) => Some(i),
+            N::F64(_) => None,
+        }
+    }
+
+    /// Integers are converted to the nearest [`f64`], which may lose precision.
+    #[must_use]
+    #[expect(clippy::cast_precision_loss)]
+    pub fn as_f64(&self) -> f64 {
+        match self.0 {
+            N::U128(u) => u as f64,
+            N::I128(i) => i as f64,
+            N::F64(f) => f,
+        }
+    }
+}
+
+macro_rules! from_unsigned {
+    ($($type:ty)*) => {
+        $(
+            impl From<$type> for Number {
+                fn from(u: $type) -> Self {
+                    Self(N::U128(u128::from(u)))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! from_signed {
+    ($($type:ty)*) => {
+        $(
+            impl From<$type> for Number {
+                fn from(i: $type) -> Self {
+                    match u128::try_from(i) {
+                        Ok(u) => Self(N::U128(u)),
+                        Err(_) => Self(N::I128(i128::from(i))),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_unsigned!(u8 u16 u32 u64 u128);
+from_signed!(i8 i16 i32 i64 i128);
+
+impl From<usize> for Number {
+    fn from(u: usize) -> Self {
+        Self(N::U128(u as u128))
+    }
+}
+
+impl From<isize> for Number {
+    fn from(i: isize) -> Self {
+        Self::from(i as i128)
+    }
+}
+
+impl From<f32> for Number {
+    fn from(f: f32) -> Self {
+        Self(N::F64(f64::from(f)))
+    }
+}
+
+impl From<f64> for Number {
+    fn from(f: f64) -> Self {
+        Self(N::F64(f))
+    }
+}
+
+impl Display for Number {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self.0 {
+            N::U128(u) => write!(f, "{u}"),
+            N::I128(i) => write!(f, "{i}"),
+            N::F64(v) => match (v.is_nan(), v.is_infinite(), v.is_sign_negative()) {
+                (true, false, false) => write!(f, "NaN"),
+                (true, false, true) => write!(f, "-NaN"),
+                (false, true, false) => write!(f, "Infinity"),
+                (false, true, true) => write!(f, "-Infinity"),
+                _ => write!(f, "{v}"),
+            },
+        }
+    }
+}
+
+macro_rules! value_from_number {
+    ($($type:ty)*) => {
+        $(
+            impl From<$type> for Value {
+                fn from(n: $type) -> Self {
+                    Value::Number(Number::from(n))
+                }
+            }
+        )*
+    };
+}
+
+value_from_number!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize f32 f64);
+
+impl From<Number> for Value {
+    fn from(n: Number) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl From<()> for Value {
+    fn from((): ()) -> Self {
+        Value::Null
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<char> for Value {
+    fn from(c: char) -> Self {
+        Value::String(c.to_string())
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(v: Vec<T>) -> Self {
+        Value::Array(v.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<Map> for Value {
+    fn from(m: Map) -> Self {
+        Value::Object(m)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(o: Option<T>) -> Self {
+        o.map_or(Value::Null, Into::into)
+    }
+}
+
+impl<T: Into<Value>> FromIterator<T> for Value {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Value::Array(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<K: Into<String>, V: Into<Value>> FromIterator<(K, V)> for Value {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Value::Object(
+            iter.into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+}
+
+/// Formats the value as JSON5 exactly as [`to_string`](crate::to_string) would.
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let s = crate::to_string(self).map_err(|_| std::fmt::Error)?;
+        f.write_str(&s)
+    }
+}
+
+/// Parses JSON5 exactly as [`from_str`](crate::from_str) would.
+impl FromStr for Value {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        crate::from_str(s)
+    }
+}
+
+static NULL: Value = Value::Null;
+
+/// Index into an array. Returns [`Value::Null`] if the index is out of bounds or if this isn't an
+/// array.
+impl Index<usize> for Value {
+    type Output = Value;
+
+    fn index(&self, index: usize) -> &Value {
+        match self {
+            Value::Array(a) => a.get(index).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+}
+
+/// Index into an array.
+///
+/// # Panics
+/// Panics if the index is out of bounds or if this isn't an array.
+impl IndexMut<usize> for Value {
+    fn index_mut(&mut self, index: usize) -> &mut Value {
+        match self {
+            Value::Array(a) => {
+                let len = a.len();
+                a.get_mut(index).unwrap_or_else(|| {
+                    panic!("cannot access index {index} of JSON5 array of length {len}")
+                })
+            }
+            _ => panic!("cannot access index {index} of JSON5 {}", self.kind()),
+        }
+    }
+}
+
+/// Index into an object. Returns [`Value::Null`] if the key is missing or if this isn't an object.
+impl Index<&str> for Value {
+    type Output = Value;
+
+    fn index(&self, key: &str) -> &Value {
+        self.get(key).unwrap_or(&NULL)
+    }
+}
+
+/// Index into an object, inserting [`Value::Null`] if the key is missing. [`Value::Null`] itself
+/// is treated as an empty object.
+///
+/// # Panics
+/// Panics if this is neither an object nor null.
+impl IndexMut<&str> for Value {
+    fn index_mut(&mut self, key: &str) -> &mut Value {
+        if self.is_null() {
+            *self = Value::Object(Map::new());
+        }
+        match self {
+            Value::Object(o) => o.entry(key.to_owned()).or_insert(Value::Null),
+            _ => panic!("cannot access key {key:?} of JSON5 {}", self.kind()),
+        }
+    }
+}
+
+impl Value {
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "bool",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        }
+    }
+}
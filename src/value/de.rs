@@ -0,0 +1,585 @@
+use std::fmt::Formatter;
+
+use serde::{
+    Deserialize,
+    de::{
+        DeserializeOwned, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+        value::{BorrowedStrDeserializer, MapAccessDeserializer, MapDeserializer, SeqDeserializer},
+    },
+    forward_to_deserialize_any,
+};
+
+use super::{Map, N, Number, Value};
+use crate::{
+    ByteEncoding, ParseOptions, bytes,
+    error::{Error, ErrorCode, Result},
+};
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "any JSON5 value")
+    }
+
+    fn visit_unit<E>(self) -> std::result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> std::result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: serde::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: serde::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_bool<E>(self, v: bool) -> std::result::Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_i128<E>(self, v: i128) -> std::result::Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_u128<E>(self, v: u128) -> std::result::Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> std::result::Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_string<E>(self, v: String) -> std::result::Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    // As written by `to_value` with the default byte encoding.
+    fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Value, E> {
+        Ok(super::ser::encode_bytes(v, ByteEncoding::default()))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Value, A::Error> {
+        let mut array = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(v) = seq.next_element()? {
+            array.push(v);
+        }
+        Ok(Value::Array(array))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Value, A::Error> {
+        let mut object = Map::new();
+        while let Some((k, v)) = map.next_entry()? {
+            object.insert(k, v);
+        }
+        Ok(Value::Object(object))
+    }
+}
+
+impl<'de> Deserialize<'de> for Number {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_any(NumberVisitor)
+    }
+}
+
+struct NumberVisitor;
+
+impl Visitor<'_> for NumberVisitor {
+    type Value = Number;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "a JSON5 number")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<Number, E> {
+        Ok(Number::from(v))
+    }
+
+    fn visit_i128<E>(self, v: i128) -> std::result::Result<Number, E> {
+        Ok(Number::from(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<Number, E> {
+        Ok(Number::from(v))
+    }
+
+    fn visit_u128<E>(self, v: u128) -> std::result::Result<Number, E> {
+        Ok(Number::from(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> std::result::Result<Number, E> {
+        Ok(Number::from(v))
+    }
+}
+
+/// Convert a [`Value`] to a type implementing [`DeserializeOwned`].
+///
+/// # Example
+/// ```
+/// use serde_derive::Deserialize;
+/// use json5::Value;
+///
+/// #[derive(Debug, PartialEq, Deserialize)]
+/// struct Config {
+///     foo: u32,
+///     bar: String,
+/// }
+///
+/// let mut value: Value = "{ foo: 42 }".parse()?;
+/// value["bar"] = Value::from("baz");
+///
+/// assert_eq!(
+///     json5::from_value::<Config>(value)?,
+///     Config { foo: 42, bar: "baz".to_owned() },
+/// );
+/// # Ok::<(), json5::Error>(())
+/// ```
+///
+/// # Errors
+/// Fails if we can't map the value to a `T`.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T> {
+    T::deserialize(value)
+}
+
+/// Convert a [`Value`] to a type implementing [`DeserializeOwned`], with the given options. Only
+/// [`ParseOptions::bytes`] applies, the rest being about parsing text. Arrays of integers are
+/// always accepted as bytes, as if [`ParseOptions::detect_bytes`] were set.
+///
+/// # Example
+/// ```
+/// use json5::{ByteEncoding, ParseOptions, Value};
+/// use serde_bytes::ByteBuf;
+///
+/// let options = ParseOptions {
+///     bytes: ByteEncoding::Base64,
+///     ..ParseOptions::default()
+/// };
+///
+/// assert_eq!(
+///     json5::from_value_with::<ByteBuf>(Value::from("SlNPTjU="), &options)?,
+///     ByteBuf::from("JSON5"),
+/// );
+/// # Ok::<(), json5::Error>(())
+/// ```
+///
+/// # Errors
+/// Fails if we can't map the value to a `T`.
+pub fn from_value_with<T: DeserializeOwned>(value: Value, options: &ParseOptions) -> Result<T> {
+    T::deserialize(Deserializer {
+        value,
+        bytes: options.bytes,
+    })
+}
+
+/// A [`Value`] (or `&Value`) with the byte encoding to read strings as bytes in.
+struct Deserializer<T> {
+    value: T,
+    bytes: ByteEncoding,
+}
+
+impl<T> Deserializer<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value,
+            bytes: ByteEncoding::default(),
+        }
+    }
+}
+
+impl IntoDeserializer<'_, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for &'de Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl IntoDeserializer<'_, Error> for Deserializer<Value> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Deserializer<&'de Value> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl Number {
+    fn visit<'de, V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            N::U128(u) => match u64::try_from(u) {
+                Ok(u) => visitor.visit_u64(u),
+                Err(_) => visitor.visit_u128(u),
+            },
+            N::I128(i) => match i64::try_from(i) {
+                Ok(i) => visitor.visit_i64(i),
+                Err(_) => visitor.visit_i128(i),
+            },
+            N::F64(f) => visitor.visit_f64(f),
+        }
+    }
+}
+
+fn decode_bytes(s: &str, encoding: ByteEncoding) -> Result<Vec<u8>> {
+    bytes::decode(s, encoding).map_err(|_| Error::new(ErrorCode::InvalidBytes))
+}
+
+// A `Value` deserializes itself with the default options.
+macro_rules! forward_to_default_options {
+    ($($method:ident($($arg:ident: $type:ty),*))*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, $($arg: $type,)* visitor: V) -> Result<V::Value> {
+                Deserializer::new(self).$method($($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+macro_rules! default_options_deserializer {
+    () => {
+        forward_to_default_options! {
+            deserialize_any() deserialize_bool() deserialize_i8() deserialize_i16()
+            deserialize_i32() deserialize_i64() deserialize_i128() deserialize_u8()
+            deserialize_u16() deserialize_u32() deserialize_u64() deserialize_u128()
+            deserialize_f32() deserialize_f64() deserialize_char() deserialize_str()
+            deserialize_string() deserialize_bytes() deserialize_byte_buf() deserialize_option()
+            deserialize_unit() deserialize_unit_struct(name: &'static str)
+            deserialize_newtype_struct(name: &'static str) deserialize_seq()
+            deserialize_tuple(len: usize) deserialize_tuple_struct(name: &'static str, len: usize)
+            deserialize_map()
+            deserialize_struct(name: &'static str, fields: &'static [&'static str])
+            deserialize_enum(name: &'static str, variants: &'static [&'static str])
+            deserialize_identifier() deserialize_ignored_any()
+        }
+    };
+}
+
+impl<'de> serde::Deserializer<'de> for Value {
+    type Error = Error;
+
+    default_options_deserializer!();
+}
+
+impl<'de> serde::Deserializer<'de> for &'de Value {
+    type Error = Error;
+
+    default_options_deserializer!();
+}
+
+impl<'de> serde::Deserializer<'de> for Deserializer<Value> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let bytes = self.bytes;
+        match self.value {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Number(n) => n.visit(visitor),
+            Value::String(s) => visitor.visit_string(s),
+            Value::Array(a) => {
+                let mut seq =
+                    SeqDeserializer::new(a.into_iter().map(|value| Deserializer { value, bytes }));
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Object(o) => {
+                let mut map = MapDeserializer::new(
+                    o.into_iter()
+                        .map(|(k, value)| (MapKey(k, bytes), Deserializer { value, bytes })),
+                );
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::String(s) => visitor.visit_byte_buf(decode_bytes(&s, self.bytes)?),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let bytes = self.bytes;
+        match self.value {
+            Value::String(s) => visitor.visit_enum(s.into_deserializer()),
+            Value::Object(o) if o.len() == 1 => MapAccessDeserializer::new(MapDeserializer::new(
+                o.into_iter()
+                    .map(|(k, value)| (MapKey(k, bytes), Deserializer { value, bytes })),
+            ))
+            .deserialize_enum(name, variants, visitor),
+            _ => Err(Error::new(ErrorCode::ExpectedStringOrObject)),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> serde::Deserializer<'de> for Deserializer<&'de Value> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let bytes = self.bytes;
+        match self.value {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(*b),
+            Value::Number(n) => n.visit(visitor),
+            Value::String(s) => visitor.visit_borrowed_str(s),
+            Value::Array(a) => {
+                let mut seq =
+                    SeqDeserializer::new(a.iter().map(|value| Deserializer { value, bytes }));
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Object(o) => {
+                let mut map =
+                    MapDeserializer::new(o.iter().map(|(k, value)| {
+                        (BorrowedMapKey(k, bytes), Deserializer { value, bytes })
+                    }));
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::String(s) => visitor.visit_byte_buf(decode_bytes(s, self.bytes)?),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let bytes = self.bytes;
+        match self.value {
+            Value::String(s) => visitor.visit_enum(BorrowedStrDeserializer::new(s)),
+            Value::Object(o) if o.len() == 1 => MapAccessDeserializer::new(MapDeserializer::new(
+                o.iter()
+                    .map(|(k, value)| (BorrowedMapKey(k, bytes), Deserializer { value, bytes })),
+            ))
+            .deserialize_enum(name, variants, visitor),
+            _ => Err(Error::new(ErrorCode::ExpectedStringOrObject)),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Object keys are always strings, so as with the JSON5 deserializer, we parse them as JSON5 when
+/// asked for some other type.
+struct MapKey(String, ByteEncoding);
+
+struct BorrowedMapKey<'de>(&'de str, ByteEncoding);
+
+impl IntoDeserializer<'_, Error> for MapKey {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for BorrowedMapKey<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_key_from_str {
+    ($method:ident, $visit:ident) => {
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            visitor.$visit(crate::from_str(&self.0)?)
+        }
+    };
+}
+
+macro_rules! map_key_deserializer {
+    ($visit_str:ident) => {
+        deserialize_key_from_str!(deserialize_bool, visit_bool);
+        deserialize_key_from_str!(deserialize_u8, visit_u8);
+        deserialize_key_from_str!(deserialize_u16, visit_u16);
+        deserialize_key_from_str!(deserialize_u32, visit_u32);
+        deserialize_key_from_str!(deserialize_u64, visit_u64);
+        deserialize_key_from_str!(deserialize_u128, visit_u128);
+        deserialize_key_from_str!(deserialize_i8, visit_i8);
+        deserialize_key_from_str!(deserialize_i16, visit_i16);
+        deserialize_key_from_str!(deserialize_i32, visit_i32);
+        deserialize_key_from_str!(deserialize_i64, visit_i64);
+        deserialize_key_from_str!(deserialize_i128, visit_i128);
+        deserialize_key_from_str!(deserialize_f32, visit_f32);
+        deserialize_key_from_str!(deserialize_f64, visit_f64);
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            visitor.$visit_str(self.0)
+        }
+
+        fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            crate::from_str::<()>(&self.0)?;
+            visitor.visit_unit()
+        }
+
+        fn deserialize_unit_struct<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            visitor: V,
+        ) -> Result<V::Value> {
+            self.deserialize_unit(visitor)
+        }
+
+        fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            // Consider keys to always be Some, otherwise we don't know if "null" is None or
+            // Some("null").
+            visitor.visit_some(self)
+        }
+
+        fn deserialize_newtype_struct<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            visitor: V,
+        ) -> Result<V::Value> {
+            visitor.visit_newtype_struct(self)
+        }
+
+        fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            visitor.visit_byte_buf(decode_bytes(&self.0, self.1)?)
+        }
+
+        fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            self.deserialize_bytes(visitor)
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            _: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value> {
+            visitor.visit_enum(self.0.into_deserializer())
+        }
+
+        forward_to_deserialize_any! {
+            char str string seq tuple tuple_struct map struct identifier ignored_any
+        }
+    };
+}
+
+impl<'de> serde::Deserializer<'de> for MapKey {
+    type Error = Error;
+
+    map_key_deserializer!(visit_string);
+}
+
+impl<'de> serde::Deserializer<'de> for BorrowedMapKey<'de> {
+    type Error = Error;
+
+    map_key_deserializer!(visit_borrowed_str);
+}
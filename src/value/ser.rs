@@ -0,0 +1,550 @@
+use serde::{
+    Serialize,
+    ser::{Impossible, SerializeMap},
+};
+
+use super::{Map, N, Number, Value};
+use crate::{
+    ByteEncoding, SerializerConfig, bytes,
+    error::{Error, ErrorCode, Result},
+};
+
+impl Serialize for Value {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Number(n) => n.serialize(serializer),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Array(a) => a.serialize(serializer),
+            Value::Object(o) => {
+                let mut map = serializer.serialize_map(Some(o.len()))?;
+                for (k, v) in o {
+                    map.serialize_entry(k, v)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl Serialize for Number {
+    // Prefer 64 bit integers where they're big enough, since not every serializer supports 128 bit
+    // integers.
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        match self.0 {
+            N::U128(u) => match u64::try_from(u) {
+                Ok(u) => serializer.serialize_u64(u),
+                Err(_) => serializer.serialize_u128(u),
+            },
+            N::I128(i) => match i64::try_from(i) {
+                Ok(i) => serializer.serialize_i64(i),
+                Err(_) => serializer.serialize_i128(i),
+            },
+            N::F64(f) => serializer.serialize_f64(f),
+        }
+    }
+}
+
+/// Convert a type implementing [`Serialize`] to a [`Value`].
+///
+/// # Example
+/// ```
+/// use serde_derive::Serialize;
+/// use json5::Value;
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     foo: u32,
+///     bar: Vec<&'static str>,
+/// }
+///
+/// let value = json5::to_value(&Config { foo: 42, bar: vec!["baz"] })?;
+///
+/// assert_eq!(value["foo"], Value::from(42));
+/// assert_eq!(value["bar"][0], Value::from("baz"));
+/// # Ok::<(), json5::Error>(())
+/// ```
+///
+/// # Errors
+/// Fails if we can't express `T` as a [`Value`] (e.g. we try to serialize an object key without an
+/// obvious string representation) or if `T`'s implementation of [`Serialize`] fails.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value> {
+    value.serialize(Serializer {
+        bytes: ByteEncoding::default(),
+    })
+}
+
+/// Convert a type implementing [`Serialize`] to a [`Value`], with the given config. Only
+/// [`SerializerConfig::bytes`] applies, the rest being about formatting text.
+///
+/// # Example
+/// ```
+/// use json5::{ByteEncoding, SerializerConfig, Value};
+/// use serde_bytes::Bytes;
+///
+/// let config = SerializerConfig {
+///     bytes: ByteEncoding::Base64,
+///     ..SerializerConfig::default()
+/// };
+///
+/// assert_eq!(
+///     json5::to_value_with(Bytes::new(b"JSON5"), &config)?,
+///     Value::from("SlNPTjU="),
+/// );
+/// # Ok::<(), json5::Error>(())
+/// ```
+///
+/// # Errors
+/// As for [`to_value`].
+pub fn to_value_with<T: Serialize + ?Sized>(value: &T, config: &SerializerConfig) -> Result<Value> {
+    value.serialize(Serializer {
+        bytes: config.bytes,
+    })
+}
+
+#[derive(Clone, Copy)]
+struct Serializer {
+    bytes: ByteEncoding,
+}
+
+macro_rules! serialize_number {
+    ($method:ident, $type:ty) => {
+        fn $method(self, v: $type) -> Result<Value> {
+            Ok(Value::from(v))
+        }
+    };
+}
+
+impl serde::Serializer for Serializer {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeVariant<SerializeArray>;
+    type SerializeMap = SerializeObject;
+    type SerializeStruct = SerializeObject;
+    type SerializeStructVariant = SerializeVariant<SerializeObject>;
+
+    serialize_number!(serialize_u8, u8);
+    serialize_number!(serialize_u16, u16);
+    serialize_number!(serialize_u32, u32);
+    serialize_number!(serialize_u64, u64);
+    serialize_number!(serialize_u128, u128);
+    serialize_number!(serialize_i8, i8);
+    serialize_number!(serialize_i16, i16);
+    serialize_number!(serialize_i32, i32);
+    serialize_number!(serialize_i64, i64);
+    serialize_number!(serialize_i128, i128);
+    serialize_number!(serialize_f32, f32);
+    serialize_number!(serialize_f64, f64);
+
+    fn serialize_bool(self, v: bool) -> Result<Value> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value> {
+        Ok(encode_bytes(v, self.bytes))
+    }
+
+    fn serialize_none(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, v: &T) -> Result<Value> {
+        v.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<Value> {
+        Ok(Value::from(variant))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        v: &T,
+    ) -> Result<Value> {
+        v.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        v: &T,
+    ) -> Result<Value> {
+        Ok(Value::Object(Map::from_iter([(
+            variant.to_owned(),
+            v.serialize(self)?,
+        )])))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeArray> {
+        Ok(SerializeArray {
+            array: Vec::with_capacity(len.unwrap_or(0)),
+            ser: self,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeArray> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, len: usize) -> Result<SerializeArray> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeArray>> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<SerializeObject> {
+        Ok(SerializeObject {
+            object: Map::new(),
+            key: None,
+            ser: self,
+        })
+    }
+
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<SerializeObject> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeObject>> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+struct SerializeArray {
+    array: Vec<Value>,
+    ser: Serializer,
+}
+
+impl serde::ser::SerializeSeq for SerializeArray {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.array.push(value.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Array(self.array))
+    }
+}
+
+impl serde::ser::SerializeTuple for SerializeArray {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        serde::ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        serde::ser::SerializeSeq::end(self)
+    }
+}
+
+impl serde::ser::SerializeTupleStruct for SerializeArray {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        serde::ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        serde::ser::SerializeSeq::end(self)
+    }
+}
+
+struct SerializeObject {
+    object: Map,
+    key: Option<String>,
+    ser: Serializer,
+}
+
+impl serde::ser::SerializeMap for SerializeObject {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+        self.key = Some(key.serialize(MapKey(self.ser.bytes))?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value is always called after serialize_key");
+        self.object.insert(key, value.serialize(self.ser)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Object(self.object))
+    }
+}
+
+impl serde::ser::SerializeStruct for SerializeObject {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        serde::ser::SerializeMap::serialize_entry(self, key, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        serde::ser::SerializeMap::end(self)
+    }
+}
+
+struct SerializeVariant<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl serde::ser::SerializeTupleVariant for SerializeVariant<SerializeArray> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        serde::ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Object(Map::from_iter([(
+            self.variant.to_owned(),
+            serde::ser::SerializeSeq::end(self.inner)?,
+        )])))
+    }
+}
+
+impl serde::ser::SerializeStructVariant for SerializeVariant<SerializeObject> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        serde::ser::SerializeMap::serialize_entry(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Object(Map::from_iter([(
+            self.variant.to_owned(),
+            serde::ser::SerializeMap::end(self.inner)?,
+        )])))
+    }
+}
+
+macro_rules! serialize_display_key {
+    ($method:ident, $type:ty) => {
+        fn $method(self, v: $type) -> Result<String> {
+            Ok(v.to_string())
+        }
+    };
+}
+
+/// Object keys must be strings, so we accept the same keys as the JSON5 serializer, and convert
+/// them to the strings it would write.
+struct MapKey(ByteEncoding);
+
+impl serde::Serializer for MapKey {
+    type Ok = String;
+    type Error = Error;
+    type SerializeSeq = Impossible<String, Error>;
+    type SerializeTuple = Impossible<String, Error>;
+    type SerializeTupleStruct = Impossible<String, Error>;
+    type SerializeTupleVariant = Impossible<String, Error>;
+    type SerializeMap = Impossible<String, Error>;
+    type SerializeStruct = Impossible<String, Error>;
+    type SerializeStructVariant = Impossible<String, Error>;
+
+    serialize_display_key!(serialize_bool, bool);
+    serialize_display_key!(serialize_u8, u8);
+    serialize_display_key!(serialize_u16, u16);
+    serialize_display_key!(serialize_u32, u32);
+    serialize_display_key!(serialize_u64, u64);
+    serialize_display_key!(serialize_u128, u128);
+    serialize_display_key!(serialize_i8, i8);
+    serialize_display_key!(serialize_i16, i16);
+    serialize_display_key!(serialize_i32, i32);
+    serialize_display_key!(serialize_i64, i64);
+    serialize_display_key!(serialize_i128, i128);
+    serialize_display_key!(serialize_char, char);
+
+    fn serialize_f32(self, v: f32) -> Result<String> {
+        Ok(Number::from(v).to_string())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<String> {
+        Ok(Number::from(v).to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String> {
+        Ok(v.to_owned())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<String> {
+        Ok(encode_bytes_string(v, self.0))
+    }
+
+    fn serialize_unit(self) -> Result<String> {
+        Ok("null".to_owned())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<String> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<String> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<String> {
+        value.serialize(self)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<String> {
+        value.serialize(self)
+    }
+
+    fn serialize_none(self) -> Result<String> {
+        Err(Error::new(ErrorCode::InvalidKey))
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<String> {
+        Err(Error::new(ErrorCode::InvalidKey))
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(Error::new(ErrorCode::InvalidKey))
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple> {
+        Err(Error::new(ErrorCode::InvalidKey))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(Error::new(ErrorCode::InvalidKey))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(Error::new(ErrorCode::InvalidKey))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(Error::new(ErrorCode::InvalidKey))
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct> {
+        Err(Error::new(ErrorCode::InvalidKey))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(Error::new(ErrorCode::InvalidKey))
+    }
+}
+
+// Byte arrays are encoded as they are by the JSON5 serializer.
+pub(super) fn encode_bytes(v: &[u8], encoding: ByteEncoding) -> Value {
+    if encoding == ByteEncoding::Array {
+        v.iter().copied().collect()
+    } else {
+        Value::String(encode_bytes_string(v, encoding))
+    }
+}
+
+fn encode_bytes_string(v: &[u8], encoding: ByteEncoding) -> String {
+    let mut s = Vec::new();
+    bytes::encode(&mut s, v, encoding).expect("writing to a Vec can't fail");
+    String::from_utf8(s).expect("encoded bytes are ASCII")
+}
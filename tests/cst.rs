@@ -0,0 +1,186 @@
+use json5::{
+    Error, ErrorCode, Position,
+    cst::{Document, TokenKind, TriviaKind, Value},
+};
+
+fn err_at(line: usize, column: usize, code: ErrorCode) -> Error {
+    Error::new_at(Position { line, column }, code)
+}
+
+// Errors compare equal whatever their spans, so check that they start where they're positioned.
+fn parse(input: &str) -> Result<Document, Error> {
+    json5::cst::parse(input).inspect_err(|err| {
+        let span = err.span().unwrap_or_else(|| panic!("no span: {err:?}"));
+        assert!(input.get(span.clone()).is_some(), "bad span: {err:?}");
+        assert_eq!(
+            err.position(),
+            Some(Position::from_offset(span.start, input))
+        );
+    })
+}
+
+#[test]
+fn round_trip() {
+    for input in [
+        "null",
+        "  true  ",
+        "'single' // comment",
+        "/* before */ 0x2A /* after */\n\n",
+        "[]",
+        "[ 1 ,2,\t3 , ]",
+        "{}",
+        "{ a: 1, 'b': +.5e1, \"c\": [null, Infinity, -NaN], }",
+        "{\r\n  a: 'line \\\r\n continuation',\r\n}\r\n",
+        "{\u{2028}\u{00A0}a\u{3000}:\u{FEFF}[/**/]\u{2029}}",
+        "{ \\u0061b: 'escaped identifier' }",
+        include_str!("chromium_example.json5"),
+    ] {
+        assert_eq!(
+            parse(input).map(|doc| doc.to_string()).as_deref(),
+            Ok(input)
+        );
+    }
+}
+
+#[test]
+fn structure() {
+    let input = "// header
+{
+  // The name.
+  name: 'json5', /* trailing */ // comments
+  list: [1, 0x2,],
+
+  'quoted': null
+}
+// footer
+";
+    let doc: Document = input.parse().unwrap();
+
+    let Value::Object(object) = &doc.value else {
+        panic!("expected an object");
+    };
+    assert_eq!(object.open.text, "{");
+    assert_eq!(
+        object
+            .open
+            .leading
+            .iter()
+            .map(|t| (t.kind, t.text.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            (TriviaKind::LineComment, "// header"),
+            (TriviaKind::Newline, "\n")
+        ]
+    );
+    assert_eq!(object.members.len(), 3);
+
+    let name = &object.members[0];
+    assert_eq!(name.key.kind, TokenKind::Identifier);
+    assert_eq!(name.key.span, Some(29..33));
+    assert_eq!(
+        name.key
+            .leading
+            .iter()
+            .map(|t| t.text.as_str())
+            .collect::<String>(),
+        "  // The name.\n  "
+    );
+    let Value::Scalar(value) = &name.value else {
+        panic!("expected a scalar");
+    };
+    assert_eq!(
+        (value.kind, value.text.as_str()),
+        (TokenKind::String, "'json5'")
+    );
+    let comma = name.comma.as_ref().unwrap();
+    assert_eq!(
+        comma.trailing.iter().map(|t| t.kind).collect::<Vec<_>>(),
+        [
+            TriviaKind::Whitespace,
+            TriviaKind::BlockComment,
+            TriviaKind::Whitespace,
+            TriviaKind::LineComment,
+            TriviaKind::Newline,
+        ]
+    );
+
+    let Value::Array(list) = &object.members[1].value else {
+        panic!("expected an array");
+    };
+    assert_eq!(list.elements.len(), 2);
+    assert_eq!(list.elements[1].value.to_string(), "0x2");
+    assert!(list.elements[1].comma.is_some());
+
+    let quoted = &object.members[2];
+    assert_eq!(quoted.key.kind, TokenKind::String);
+    assert_eq!(quoted.key.text, "'quoted'");
+    assert_eq!(quoted.key.leading[0].kind, TriviaKind::Newline);
+    assert!(quoted.comma.is_none());
+
+    assert_eq!(
+        doc.trailing
+            .iter()
+            .map(|t| t.text.as_str())
+            .collect::<String>(),
+        "// footer\n"
+    );
+}
+
+#[test]
+fn edit_and_print() {
+    let mut doc = parse("{ version: '1.0.0', /* keep me */ }").unwrap();
+    let Value::Object(object) = &mut doc.value else {
+        panic!("expected an object");
+    };
+    let Value::Scalar(version) = &mut object.members[0].value else {
+        panic!("expected a scalar");
+    };
+    version.text = "'1.1.0'".to_owned();
+    version.span = None;
+    assert_eq!(doc.to_string(), "{ version: '1.1.0', /* keep me */ }");
+}
+
+#[test]
+fn errors() {
+    assert_eq!(parse(""), Err(err_at(0, 0, ErrorCode::EofParsingValue)));
+    assert_eq!(parse("1 /").map_err(|err| err.span()), Err(Some(2..3)));
+    assert_eq!(parse("[1 2]"), Err(err_at(0, 3, ErrorCode::ExpectedComma)));
+    assert_eq!(
+        parse("[1, , 2]"),
+        Err(err_at(0, 4, ErrorCode::ExpectedValue))
+    );
+    assert_eq!(parse("[1,"), Err(err_at(0, 0, ErrorCode::EofParsingArray)));
+    assert_eq!(
+        parse("{ a 1 }"),
+        Err(err_at(0, 4, ErrorCode::ExpectedColon))
+    );
+    assert_eq!(parse("{ a: }"), Err(err_at(0, 5, ErrorCode::ExpectedValue)));
+    assert_eq!(
+        parse("{ a:"),
+        Err(err_at(0, 0, ErrorCode::EofParsingObject))
+    );
+    assert_eq!(
+        parse("{ 1: 2 }"),
+        Err(err_at(0, 2, ErrorCode::ExpectedIdentifier))
+    );
+    assert_eq!(parse("[tru]"), Err(err_at(0, 4, ErrorCode::ExpectedBool)));
+    assert_eq!(parse("[0x]"), Err(err_at(0, 3, ErrorCode::ExpectedNumber)));
+    assert_eq!(parse("1 /x"), Err(err_at(0, 3, ErrorCode::ExpectedComment)));
+    assert_eq!(
+        parse("[] []"),
+        Err(err_at(0, 3, ErrorCode::TrailingCharacters))
+    );
+    assert_eq!(
+        parse(&"[".repeat(1000)),
+        Err(err_at(0, 128, ErrorCode::RecursionLimitExceeded))
+    );
+    assert_eq!(parse("[1, 'abc").map_err(|err| err.span()), Err(Some(4..8)));
+
+    // Errors are the same as from deserializing.
+    for input in ["{ a: 'b\\u12' }", "[1, 2] x", "{ a: 1 b: 2 }"] {
+        assert_eq!(
+            parse(input).err(),
+            json5::from_str::<json5::Value>(input).err()
+        );
+    }
+}
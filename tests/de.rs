@@ -1,16 +1,16 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, io};
 
-use json5::{Error, ErrorCode, Position, from_str};
+use json5::{
+    ByteEncoding, Deserializer, Dialect, DuplicateKeys, Error, ErrorCode, ParseOptions, Position,
+    from_reader, from_slice,
+};
 
 use ErrorCode::*;
+use serde::{Deserialize as _, de::IgnoredAny};
 use serde_bytes::ByteBuf;
 use serde_derive::Deserialize;
 use serde_json::json;
 
-fn err(code: ErrorCode) -> Error {
-    Error::new(code)
-}
-
 fn err_at(line: usize, column: usize, code: ErrorCode) -> Error {
     Error::new_at(Position { line, column }, code)
 }
@@ -19,6 +19,22 @@ fn custom_err_at(line: usize, column: usize, msg: &str) -> Error {
     Error::custom_at(Position { line, column }, msg)
 }
 
+// Errors compare equal whatever their spans, so the errors these tests compare are also checked to
+// have a span starting where the error is positioned. `spans` checks where some of them end.
+fn check_span(input: &str, err: &Error) {
+    let span = err.span().unwrap_or_else(|| panic!("no span: {err:?}"));
+    assert!(input.get(span.clone()).is_some(), "bad span: {err:?}");
+    assert_eq!(
+        err.position(),
+        Some(Position::from_offset(span.start, input)),
+        "span doesn't match position: {err:?}"
+    );
+}
+
+fn from_str<'de, T: serde::Deserialize<'de>>(input: &'de str) -> Result<T, Error> {
+    json5::from_str(input).inspect_err(|err| check_span(input, err))
+}
+
 // https://262.ecma-international.org/5.1/#sec-7.8.1
 #[test]
 fn parse_null() {
@@ -30,7 +46,7 @@ fn parse_null() {
     assert_eq!(from_str::<()>("false"), Err(err_at(0, 0, ExpectedNull)));
     assert_eq!(from_str::<()>("nil"), Err(err_at(0, 1, ExpectedNull)));
     assert_eq!(from_str::<()>("0"), Err(err_at(0, 0, ExpectedNull)));
-    assert_eq!(from_str::<()>("n"), Err(err(EofParsingNull)));
+    assert_eq!(from_str::<()>("n"), Err(err_at(0, 0, EofParsingNull)));
 }
 
 // https://262.ecma-international.org/5.1/#sec-7.8.2
@@ -45,7 +61,7 @@ fn parse_bool() {
     assert_eq!(from_str::<bool>("null"), Err(err_at(0, 0, ExpectedBool)));
     assert_eq!(from_str::<bool>("yes"), Err(err_at(0, 0, ExpectedBool)));
     assert_eq!(from_str::<bool>("0"), Err(err_at(0, 0, ExpectedBool)));
-    assert_eq!(from_str::<bool>("t"), Err(err(EofParsingBool)));
+    assert_eq!(from_str::<bool>("t"), Err(err_at(0, 0, EofParsingBool)));
 }
 
 // https://spec.json5.org/#numbers
@@ -99,7 +115,7 @@ fn parse_number() {
         Ok(i128::MIN)
     );
 
-    assert_eq!(from_str::<u32>("0x"), Err(err(EofParsingNumber)));
+    assert_eq!(from_str::<u32>("0x"), Err(err_at(0, 0, EofParsingNumber)));
     assert_eq!(from_str::<u32>("0x!"), Err(err_at(0, 2, ExpectedNumber)));
     assert_eq!(from_str::<f64>("inf"), Err(err_at(0, 0, ExpectedNumber)));
     assert_eq!(
@@ -215,7 +231,10 @@ fn parse_string() {
         from_str::<String>("false"),
         Err(err_at(0, 0, ExpectedString))
     );
-    assert_eq!(from_str::<String>("'..."), Err(err(EofParsingString)));
+    assert_eq!(
+        from_str::<String>("'..."),
+        Err(err_at(0, 0, EofParsingString))
+    );
     assert_eq!(
         from_str::<&str>(r#""two\nlines""#),
         Err(custom_err_at(
@@ -594,11 +613,11 @@ fn bytes() {
 
     assert_eq!(
         from_str::<ByteBuf>("'4a534f4e3'"),
-        Err(err_at(0, 0, InvalidBytes))
+        Err(err_at(0, 10, InvalidBytes))
     );
     assert_eq!(
         from_str::<HashMap<ByteBuf, bool>>("{ '4a534f4e3g': true }"),
-        Err(err_at(0, 2, InvalidBytes))
+        Err(err_at(0, 12, InvalidBytes))
     );
     assert_eq!(
         from_str::<&[u8]>("'4a534f4e35'"),
@@ -610,6 +629,142 @@ fn bytes() {
     );
 }
 
+#[test]
+fn byte_encodings() {
+    fn parse_with<T: serde::de::DeserializeOwned>(
+        input: &str,
+        options: ParseOptions,
+    ) -> Result<T, Error> {
+        let mut de = Deserializer::from_str(input).with_options(options);
+        let t = T::deserialize(&mut de).and_then(|t| de.end().map(|()| t));
+        t.inspect_err(|err| check_span(input, err))
+    }
+
+    fn parse<T: serde::de::DeserializeOwned>(input: &str, bytes: ByteEncoding) -> Result<T, Error> {
+        parse_with(
+            input,
+            ParseOptions {
+                bytes,
+                ..ParseOptions::default()
+            },
+        )
+    }
+
+    fn detect<T: serde::de::DeserializeOwned>(
+        input: &str,
+        bytes: ByteEncoding,
+    ) -> Result<T, Error> {
+        parse_with(
+            input,
+            ParseOptions {
+                bytes,
+                detect_bytes: true,
+                ..ParseOptions::default()
+            },
+        )
+    }
+
+    let json5 = Ok(ByteBuf::from("JSON5"));
+    assert_eq!(parse("'SlNPTjU='", ByteEncoding::Base64), json5);
+    assert_eq!(parse("'SlNPTjU'", ByteEncoding::Base64), json5);
+    assert_eq!(parse("'SlNPTjU'", ByteEncoding::Base64UrlSafe), json5);
+    assert_eq!(
+        parse("'-_8'", ByteEncoding::Base64UrlSafe),
+        Ok(ByteBuf::from([0xfb, 0xff]))
+    );
+    assert_eq!(parse("[74, 83, 79, 0x4e, 53,]", ByteEncoding::Array), json5);
+    assert_eq!(parse("[]", ByteEncoding::Array), Ok(ByteBuf::new()));
+    assert_eq!(
+        parse("{ 'SlNPTjU': 1 }", ByteEncoding::Base64),
+        Ok(HashMap::from([(ByteBuf::from("JSON5"), 1)]))
+    );
+    // Keys are strings, so are hex when the encoding is arrays.
+    assert_eq!(
+        parse(
+            "{ '4a534f4e35': [74, 83, 79, 78, 53] }",
+            ByteEncoding::Array
+        ),
+        Ok(HashMap::from([(
+            ByteBuf::from("JSON5"),
+            ByteBuf::from("JSON5")
+        )]))
+    );
+
+    // The encoding isn't guessed: this is hex for [0xaa, 0xaa] and base64 for [0, 0, 0].
+    assert_eq!(
+        parse("'AAAA'", ByteEncoding::Hex),
+        Ok(ByteBuf::from([0xaa, 0xaa]))
+    );
+    assert_eq!(
+        parse("'AAAA'", ByteEncoding::Base64),
+        Ok(ByteBuf::from([0, 0, 0]))
+    );
+    assert_eq!(
+        parse::<ByteBuf>("'SlNPTjU='", ByteEncoding::Hex),
+        Err(err_at(0, 1, InvalidBytes))
+    );
+    assert_eq!(
+        parse::<ByteBuf>("[74]", ByteEncoding::Hex),
+        Err(err_at(0, 0, ExpectedString))
+    );
+
+    // Detecting arrays. Strings are still read in the given encoding, so 'AAAA' is ambiguous.
+    assert_eq!(detect("[74, 83, 79, 78, 53]", ByteEncoding::Hex), json5);
+    assert_eq!(detect("'4a534f4e35'", ByteEncoding::Hex), json5);
+    assert_eq!(detect("'SlNPTjU='", ByteEncoding::Base64), json5);
+    assert_eq!(detect("'4a534f4e35'", ByteEncoding::Array), json5);
+    assert_eq!(
+        detect("['AAAA', [0]]", ByteEncoding::Hex),
+        Ok([ByteBuf::from([0xaa, 0xaa]), ByteBuf::from([0])])
+    );
+    assert_eq!(
+        detect("['AAAA', [0]]", ByteEncoding::Base64),
+        Ok([ByteBuf::from([0, 0, 0]), ByteBuf::from([0])])
+    );
+    assert_eq!(
+        detect::<ByteBuf>("[74, 256]", ByteEncoding::Base64),
+        Err(err_at(0, 5, InvalidBytes))
+    );
+    assert_eq!(
+        detect::<ByteBuf>("'SlNPTjU='", ByteEncoding::Hex),
+        Err(err_at(0, 1, InvalidBytes))
+    );
+
+    // Errors point at the offending character.
+    assert_eq!(
+        parse::<ByteBuf>("'SlNP+jU'", ByteEncoding::Base64UrlSafe),
+        Err(err_at(0, 5, InvalidBytes))
+    );
+    assert_eq!(
+        parse::<ByteBuf>("'SlNPTjV='", ByteEncoding::Base64),
+        Err(err_at(0, 7, InvalidBytes))
+    );
+    assert_eq!(
+        parse::<ByteBuf>("'SlNPT'", ByteEncoding::Base64),
+        Err(err_at(0, 6, InvalidBytes))
+    );
+    assert_eq!(
+        parse::<ByteBuf>("'SlNPTjU==='", ByteEncoding::Base64),
+        Err(err_at(0, 8, InvalidBytes))
+    );
+    assert_eq!(
+        parse::<ByteBuf>("'4a534f4e35'", ByteEncoding::Array),
+        Err(err_at(0, 0, ExpectedOpeningBracket))
+    );
+    assert_eq!(
+        parse::<ByteBuf>("[74,\n 256]", ByteEncoding::Array),
+        Err(err_at(1, 1, InvalidBytes))
+    );
+    assert_eq!(
+        parse::<ByteBuf>("[74, -1]", ByteEncoding::Array),
+        Err(err_at(0, 5, InvalidBytes))
+    );
+    assert_eq!(
+        parse::<HashMap<String, ByteBuf>>("{ a: 'JSON 5' }", ByteEncoding::Base64),
+        Err(err_at(0, 10, InvalidBytes))
+    );
+}
+
 #[test]
 fn trailing_characters() {
     assert_eq!(
@@ -618,6 +773,727 @@ fn trailing_characters() {
     );
 }
 
+#[test]
+fn from_slice_and_reader() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct S<'a> {
+        a: &'a str,
+    }
+
+    assert_eq!(from_slice(b"{ a: 'b' }"), Ok(S { a: "b" }));
+    assert_eq!(
+        from_reader(&b"{ 'a': 'b\\nc', d: [1, 2] }"[..]),
+        Ok(json!({ "a": "b\nc", "d": [1, 2] }))
+    );
+    assert_eq!(
+        from_reader::<_, String>(&b"'borrowed'"[..]),
+        Ok("borrowed".to_owned())
+    );
+    assert_eq!(
+        from_reader::<_, bool>(&b"true false"[..]),
+        Err(err_at(0, 5, TrailingCharacters))
+    );
+
+    assert_eq!(
+        from_reader(Trickle(b"['caf\xc3\xa9', '\xe2\x82\xac']", 1)),
+        Ok(["café".to_owned(), "€".to_owned()])
+    );
+
+    // InvalidUtf8 errors span the invalid bytes.
+    for input in [&b"'\xff'"[..], b"[\n  'caf\xc3\xa9 \xc3'\n]", b"'\xe2\x82"] {
+        let errors = [
+            from_slice::<serde_json::Value>(input).unwrap_err(),
+            from_reader::<_, serde_json::Value>(input).unwrap_err(),
+            from_reader::<_, serde_json::Value>(Trickle(input, 1)).unwrap_err(),
+        ];
+        for err in &errors {
+            assert_eq!(err, &errors[0]);
+            assert_eq!(
+                (err.code(), err.span()),
+                (Some(InvalidUtf8), errors[0].span())
+            );
+        }
+    }
+    assert_eq!(
+        from_slice::<String>(b"'\xff'").map_err(|err| err.span()),
+        Err(Some(1..2))
+    );
+    assert_eq!(
+        from_reader::<_, Vec<String>>(&b"[\n  'caf\xc3\xa9 \xc3'\n]"[..]),
+        Err(err_at(1, 8, InvalidUtf8))
+    );
+    assert_eq!(
+        from_reader::<_, String>(&b"'\xe2\x82"[..]).map_err(|err| err.span()),
+        Err(Some(1..3))
+    );
+
+    // Read errors are positioned at the end of what was read.
+    let failing = io::Read::chain(&b"[1, 2"[..], Failing);
+    assert_eq!(
+        from_reader::<_, Vec<u32>>(failing),
+        Err(custom_err_at(0, 5, "failed"))
+    );
+
+    let mut de = Deserializer::from_reader(&b"1 // trailing comment"[..]);
+    assert_eq!(serde::Deserialize::deserialize(&mut de), Ok(1));
+    assert_eq!(de.end(), Ok(()));
+}
+
+// Reads a few bytes at a time, splitting tokens and chars between reads.
+struct Trickle<'a>(&'a [u8], usize);
+
+impl io::Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.0.len().min(self.1).min(buf.len());
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+struct Failing;
+
+impl io::Read for Failing {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::other("failed"))
+    }
+}
+
+// Large enough inputs for the reader to discard what it's done with, which mustn't change the
+// results (or where errors are).
+#[test]
+fn from_reader_large_input() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Item {
+        id: u32,
+        name: String,
+        tags: Option<Vec<u8>>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Doc {
+        version: u32,
+        items: Vec<Item>,
+    }
+
+    fn check<T: serde::de::DeserializeOwned + PartialEq + std::fmt::Debug>(input: &str) {
+        let expected = from_str::<T>(input);
+        for res in [
+            from_reader::<_, T>(input.as_bytes()),
+            from_reader::<_, T>(Trickle(input.as_bytes(), 7)),
+        ] {
+            assert_eq!(res, expected);
+            if let (Err(err), Err(expected)) = (res, &expected) {
+                assert_eq!((err.span(), err.path()), (expected.span(), expected.path()));
+            }
+        }
+    }
+
+    let items = (0..5000)
+        .map(|i| format!("  {{ id: {i}, name: 'caf\u{e9} {i}', tags: [1, 2] }},\r\n"))
+        .collect::<String>();
+    let input = format!("{{\r\n version: 1, // comment\r\n items: [\r\n{items}] }}");
+    check::<Doc>(&input);
+    check::<serde_json::Value>(&input);
+
+    // Errors are positioned the same, however far back in the input they are.
+    let last = "{ id: 5000, name: 'last', tags: null }";
+    check::<Doc>(&input.replace("version: 1,", ""));
+    check::<Doc>(&input.replace("id: 4999,", "id: -1,"));
+    check::<Doc>(&input.replace("] }", &format!("{last}, {{ id: 'x' }}] }}")));
+    check::<Doc>(&input.replace("] }", &format!("{last}, {{ id: 1, extra: 1 }}] }}")));
+    check::<Doc>(&input.replace("] }", "{ id: 1, name: 'x', tags: [1, 256] }] }"));
+    check::<Doc>(&input.replace("] }", "]"));
+    check::<Doc>(&input.replace("] }", "] } x"));
+    check::<Option<Vec<Item>>>(&format!("[{items}"));
+    check::<HashMap<String, serde_json::Value>>(&input.replace("version: 1,", "version: 'x'"));
+
+    let options = |duplicate_keys| ParseOptions {
+        duplicate_keys,
+        ..ParseOptions::default()
+    };
+    let input = format!("{{ a: [{items}], b: 1, a: 2 }}");
+    for duplicate_keys in [
+        DuplicateKeys::Error,
+        DuplicateKeys::FirstWins,
+        DuplicateKeys::LastWins,
+    ] {
+        let expected = HashMap::<String, serde_json::Value>::deserialize(
+            &mut Deserializer::from_str(&input).with_options(options(duplicate_keys)),
+        );
+        let res = HashMap::<String, serde_json::Value>::deserialize(
+            &mut Deserializer::from_reader(Trickle(input.as_bytes(), 100))
+                .with_options(options(duplicate_keys)),
+        );
+        assert_eq!(res, expected);
+    }
+    let err = HashMap::<String, serde_json::Value>::deserialize(
+        &mut Deserializer::from_reader(input.as_bytes())
+            .with_options(options(DuplicateKeys::Error)),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.code(),
+        Some(DuplicateKey {
+            first: Position { line: 0, column: 2 }
+        })
+    );
+
+    // Streams and validation too.
+    let input = items.replace(",\r\n", "\n");
+    let values = |de: Deserializer| {
+        de.into_iter::<Item>()
+            .map(|res| res.map(|(offset, item)| (offset, item.id)))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    assert_eq!(
+        values(Deserializer::from_reader(Trickle(input.as_bytes(), 7))),
+        values(Deserializer::from_str(&input))
+    );
+    assert_eq!(
+        values(Deserializer::from_reader(input.as_bytes()))
+            .unwrap()
+            .len(),
+        5000
+    );
+    let input = format!("[{{ a: tru }}, {items} {{ b: [1 2] }}, ");
+    let errors = Deserializer::from_reader(input.as_bytes()).validate();
+    assert_eq!(errors, json5::validate(&input));
+    assert_eq!(
+        errors.iter().map(Error::span).collect::<Vec<_>>(),
+        json5::validate(&input)
+            .iter()
+            .map(Error::span)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn stream() {
+    fn stream(input: &str) -> Vec<Result<(usize, serde_json::Value), Error>> {
+        let values: Vec<_> = Deserializer::from_str(input).into_iter().collect();
+        for err in values.iter().filter_map(|res| res.as_ref().err()) {
+            check_span(input, err);
+        }
+        values
+    }
+
+    assert_eq!(stream(""), []);
+    assert_eq!(stream(" // nothing here\n"), []);
+    assert_eq!(
+        stream("{ a: 1 }\n{ a: 2, }\n"),
+        [Ok((0, json!({ "a": 1 }))), Ok((9, json!({ "a": 2 })))]
+    );
+    assert_eq!(
+        stream("1 /* two */ 'three'[4]{}null"),
+        [
+            Ok((0, json!(1))),
+            Ok((12, json!("three"))),
+            Ok((19, json!([4]))),
+            Ok((22, json!({}))),
+            Ok((24, json!(null))),
+        ]
+    );
+    assert_eq!(
+        stream("true\n{ a: }\nfalse"),
+        [Ok((0, json!(true))), Err(err_at(1, 5, ExpectedValue))]
+    );
+    assert_eq!(stream("truefalse"), [Err(err_at(0, 4, TrailingCharacters))]);
+
+    let mut values = Deserializer::from_str("1 2 x 3").into_iter::<u8>();
+    assert_eq!(values.next(), Some(Ok((0, 1))));
+    assert_eq!(values.next(), Some(Ok((2, 2))));
+    assert_eq!(values.next(), Some(Err(err_at(0, 4, ExpectedNumber))));
+    assert_eq!(values.next(), None);
+}
+
+#[test]
+fn json_dialect() {
+    fn from_json(input: &str) -> Result<serde_json::Value, Error> {
+        let mut de = Deserializer::from_str(input).with_options(ParseOptions {
+            dialect: Dialect::Json,
+            ..ParseOptions::default()
+        });
+        let value = serde::Deserialize::deserialize(&mut de).and_then(|v| de.end().map(|()| v));
+        value.inspect_err(|err| check_span(input, err))
+    }
+
+    assert_eq!(
+        from_json(
+            r#"{
+                "a": [1, -2.5, 3e2, 0.5E-1, 0, -0],
+                "b": "tab\t quote\" slash\/ unicode\u00e9 \u2028",
+                "c": { "d": null, "e": [true, false], "f": {} }
+            }"#
+        ),
+        Ok(json!({
+            "a": [1, -2.5, 300.0, 0.05, 0, 0],
+            "b": "tab\t quote\" slash/ unicode\u{e9} \u{2028}",
+            "c": { "d": null, "e": [true, false], "f": {} },
+        }))
+    );
+
+    assert_eq!(from_json("[1] // hi"), Err(err_at(0, 4, CommentInJson)));
+    assert_eq!(from_json("/* hi */ 1"), Err(err_at(0, 0, CommentInJson)));
+    assert_eq!(from_json("{ a: 1 }"), Err(err_at(0, 2, UnquotedKeyInJson)));
+    assert_eq!(from_json("['a']"), Err(err_at(0, 1, SingleQuotesInJson)));
+    assert_eq!(
+        from_json("{ 'a': 1 }"),
+        Err(err_at(0, 2, SingleQuotesInJson))
+    );
+    assert_eq!(from_json("-0xff"), Err(err_at(0, 0, HexNumberInJson)));
+    assert_eq!(from_json("[Infinity]"), Err(err_at(0, 1, InfinityInJson)));
+    assert_eq!(from_json("-NaN"), Err(err_at(0, 0, NanInJson)));
+    assert_eq!(from_json("+1"), Err(err_at(0, 0, PlusSignInJson)));
+    assert_eq!(
+        from_json("-.5"),
+        Err(err_at(0, 1, LeadingDecimalPointInJson))
+    );
+    assert_eq!(
+        from_json("5."),
+        Err(err_at(0, 1, TrailingDecimalPointInJson))
+    );
+    assert_eq!(
+        from_json("5.e1"),
+        Err(err_at(0, 1, TrailingDecimalPointInJson))
+    );
+    assert_eq!(from_json("[1, 2,]"), Err(err_at(0, 5, TrailingCommaInJson)));
+    assert_eq!(
+        from_json("{ \"a\": 1, }"),
+        Err(err_at(0, 8, TrailingCommaInJson))
+    );
+    assert_eq!(
+        from_json("\"a\\\nb\""),
+        Err(err_at(0, 2, LineContinuationInJson))
+    );
+    assert_eq!(
+        from_json("\"\\x41\""),
+        Err(err_at(0, 1, EscapeSequenceInJson))
+    );
+    assert_eq!(
+        from_json("\"\\'\""),
+        Err(err_at(0, 1, EscapeSequenceInJson))
+    );
+    assert_eq!(
+        from_json("\"a\tb\""),
+        Err(err_at(0, 2, ControlCharacterInJson))
+    );
+    assert_eq!(from_json("\u{00a0}1"), Err(err_at(0, 0, WhitespaceInJson)));
+
+    // The default is still JSON5.
+    let mut de = Deserializer::from_str("[+.5,]").with_options(ParseOptions::default());
+    assert_eq!(serde::Deserialize::deserialize(&mut de), Ok(json!([0.5])));
+}
+
+#[test]
+fn recursion_limit() {
+    fn nested(depth: usize) -> String {
+        "[{ a: ".repeat(depth / 2) + "null" + &"}]".repeat(depth / 2)
+    }
+
+    assert!(from_str::<IgnoredAny>(&nested(128)).is_ok());
+    assert_eq!(
+        from_str::<IgnoredAny>(&nested(130)),
+        Err(err_at(0, 384, RecursionLimitExceeded))
+    );
+    assert_eq!(
+        from_str::<IgnoredAny>(&"[".repeat(100_000)),
+        Err(err_at(0, 128, RecursionLimitExceeded))
+    );
+
+    let deserialize = |input: &str, max_depth| {
+        let mut de = Deserializer::from_str(input).with_options(ParseOptions {
+            max_depth,
+            ..ParseOptions::default()
+        });
+        IgnoredAny::deserialize(&mut de)
+            .map(|_| ())
+            .inspect_err(|err| check_span(input, err))
+    };
+    assert_eq!(
+        deserialize("[[1], { a: [2] }]", Some(2)),
+        Err(err_at(0, 11, RecursionLimitExceeded))
+    );
+    assert_eq!(deserialize("[[1], { a: 2 }]", Some(2)), Ok(()));
+    assert_eq!(deserialize(&nested(1000), None), Ok(()));
+
+    #[derive(Debug, Deserialize)]
+    enum E {
+        A(Vec<u8>),
+    }
+    assert_eq!(
+        deserialize("{ A: [1] }", Some(1)),
+        Err(err_at(0, 5, RecursionLimitExceeded))
+    );
+    let mut de = Deserializer::from_str("{ A: [1] }").with_options(ParseOptions {
+        max_depth: Some(1),
+        ..ParseOptions::default()
+    });
+    assert_eq!(
+        E::deserialize(&mut de).map(|E::A(v)| v),
+        Err(err_at(0, 5, RecursionLimitExceeded))
+    );
+}
+
+#[test]
+fn duplicate_keys() {
+    fn deserialize<'de, T: serde::Deserialize<'de>>(
+        input: &'de str,
+        duplicate_keys: DuplicateKeys,
+    ) -> Result<T, Error> {
+        let mut de = Deserializer::from_str(input).with_options(ParseOptions {
+            duplicate_keys,
+            ..ParseOptions::default()
+        });
+        let t = T::deserialize(&mut de).and_then(|t| de.end().map(|()| t));
+        t.inspect_err(|err| check_span(input, err))
+    }
+
+    let input = "{ a: 1, b: { c: 2, c: 3 }, 'a': 4, \\u0061: 5, d: 6 }";
+    assert_eq!(
+        deserialize(input, DuplicateKeys::Allow),
+        Ok(json!({ "a": 5, "b": { "c": 3 }, "d": 6 }))
+    );
+    assert_eq!(
+        deserialize(input, DuplicateKeys::FirstWins),
+        Ok(json!({ "a": 1, "b": { "c": 2 }, "d": 6 }))
+    );
+    assert_eq!(
+        deserialize(input, DuplicateKeys::LastWins),
+        Ok(json!({ "a": 5, "b": { "c": 3 }, "d": 6 }))
+    );
+
+    let err = deserialize::<serde_json::Value>(input, DuplicateKeys::Error).unwrap_err();
+    assert_eq!(
+        err,
+        err_at(
+            0,
+            19,
+            DuplicateKey {
+                first: Position {
+                    line: 0,
+                    column: 13
+                }
+            }
+        )
+    );
+    assert_eq!(
+        err.to_string(),
+        "b: duplicate key (first occurrence at line 1 column 14) at line 1 column 20"
+    );
+
+    // The policy applies whatever the target type.
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct S {
+        a: u8,
+    }
+    assert_eq!(
+        deserialize::<S>("{ a: 1, a: 2 }", DuplicateKeys::Allow),
+        Err(custom_err_at(0, 0, "duplicate field `a`"))
+    );
+    assert_eq!(
+        deserialize("{ a: 1, a: 2 }", DuplicateKeys::FirstWins),
+        Ok(S { a: 1 })
+    );
+    assert_eq!(
+        deserialize("{ a: 1, a: 2 }", DuplicateKeys::LastWins),
+        Ok(S { a: 2 })
+    );
+    assert_eq!(
+        deserialize::<HashMap<String, u8>>("{ a: 1, a: 2 }", DuplicateKeys::Error),
+        Err(err_at(
+            0,
+            8,
+            DuplicateKey {
+                first: Position { line: 0, column: 2 }
+            }
+        ))
+    );
+
+    // Skipped values still have to be valid.
+    assert_eq!(
+        deserialize::<S>("{ a: 1, a: [} }", DuplicateKeys::FirstWins),
+        Err(err_at(0, 12, ExpectedValue))
+    );
+    assert_eq!(
+        deserialize::<S>("{ a: [}, a: 1 }", DuplicateKeys::LastWins),
+        Err(err_at(0, 5, ExpectedNumber))
+    );
+
+    // Each object is only scanned once, however deeply it's nested (this would take years
+    // otherwise).
+    let input = format!("{}1{}", "{a:".repeat(100), "}".repeat(100));
+    let value: serde_json::Value = deserialize(&input, DuplicateKeys::LastWins).unwrap();
+    assert_eq!(value.pointer(&"/a".repeat(100)), Some(&json!(1)));
+    assert_eq!(
+        deserialize(
+            "[{ a: [{ b: 1, b: 2 }], a: [{ b: 3, b: 4 }, { c: 5, c: 6 }] }]",
+            DuplicateKeys::LastWins
+        ),
+        Ok(json!([{ "a": [{ "b": 4 }, { "c": 6 }] }]))
+    );
+}
+
+#[test]
+fn spans() {
+    fn span<'de, T: serde::Deserialize<'de>>(input: &'de str) -> Option<std::ops::Range<usize>> {
+        from_str::<T>(input).err().and_then(|err| err.span())
+    }
+
+    assert_eq!(span::<bool>("\n  tru"), Some(3..6));
+    assert_eq!(span::<bool>("\n  trux"), Some(6..7));
+    assert_eq!(span::<Vec<u8>>("[1, é]"), Some(4..6));
+    assert_eq!(span::<u8>("  1e999999"), Some(2..10));
+    assert_eq!(
+        span::<i128>("-0xffffffffffffffffffffffffffffffff"),
+        Some(0..35)
+    );
+    assert_eq!(span::<String>("'a\\u00zz'"), Some(2..7));
+    assert_eq!(span::<ByteBuf>("['4a5', 1]"), Some(0..1));
+    assert_eq!(span::<(ByteBuf,)>("['4a5']"), Some(5..6));
+    assert_eq!(span::<(ByteBuf,)>("['4a\\u0035']"), Some(1..11));
+    assert_eq!(span::<u8>("{ a: 1 }"), Some(0..1));
+    assert_eq!(span::<Vec<u8>>("[1, 256]"), Some(4..7));
+    assert_eq!(span::<bool>("true false"), Some(5..6));
+    assert_eq!(span::<HashMap<u32, u32>>("{ abc: 1 }"), Some(2..5));
+
+    // EOF errors span from the start of the token they're in to the end of the input.
+    assert_eq!(span::<String>("'abc"), Some(0..4));
+    assert_eq!(span::<u32>("1 /"), Some(2..3));
+    assert_eq!(span::<u32>(""), Some(0..0));
+    assert_eq!(span::<Vec<u32>>("[1, 2"), Some(0..5));
+
+    let err = from_str::<Vec<u32>>("[\n  1,\n  -1,\n]").unwrap_err();
+    assert_eq!(err.position(), Some(Position { line: 2, column: 2 }));
+    assert_eq!(err.offset(), Some(9));
+    assert_eq!(err.span(), Some(9..11));
+
+    // Spans aren't compared.
+    assert_eq!(
+        err,
+        custom_err_at(2, 2, "invalid value: integer `-1`, expected u32")
+    );
+}
+
+#[test]
+fn paths() {
+    #[derive(Debug, Deserialize)]
+    struct Config {
+        #[expect(dead_code)]
+        servers: Vec<Server>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[expect(dead_code)]
+    struct Server {
+        tls: Tls,
+        mode: Option<Mode>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[expect(dead_code)]
+    struct Tls {
+        port: u16,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[expect(dead_code)]
+    enum Mode {
+        Fast(u8),
+        Slow { delay: u8 },
+    }
+
+    let err = from_str::<Config>("{ servers: [{ tls: { port: 443 } }, { tls: { port: 'x' } }] }")
+        .unwrap_err();
+    assert_eq!(err.path(), Some("servers[1].tls.port"));
+    assert_eq!(
+        err.to_string(),
+        "servers[1].tls.port: expected number at line 1 column 52"
+    );
+
+    let err =
+        from_str::<Config>("{ servers: [{ tls: { port: 1 }, mode: { Slow: { delay: -1 } } }] }")
+            .unwrap_err();
+    assert_eq!(err.path(), Some("servers[0].mode.Slow.delay"));
+
+    let err =
+        from_str::<Config>("{ servers: [{ tls: { port: 1 }, mode: { Fast: [] } }] }").unwrap_err();
+    assert_eq!(err.path(), Some("servers[0].mode.Fast"));
+
+    let err = from_str::<HashMap<String, Vec<u8>>>("{ 'a b': [1, 2, 300] }").unwrap_err();
+    assert_eq!(err.path(), Some("[\"a b\"][2]"));
+
+    // Paths aren't compared.
+    assert_eq!(
+        from_str::<Vec<u8>>("[1, true]"),
+        Err(err_at(0, 4, ExpectedNumber))
+    );
+
+    // Errors at the root have no path.
+    assert_eq!(from_str::<u8>("true").unwrap_err().path(), None);
+    assert_eq!(from_str::<Vec<u8>>("[1] 2").unwrap_err().path(), None);
+}
+
+#[test]
+fn validate() {
+    fn validate(input: &str) -> Vec<Error> {
+        let errors = json5::validate(input);
+        for err in &errors {
+            check_span(input, err);
+        }
+        errors
+    }
+
+    fn codes(errors: &[Error]) -> Vec<(Option<usize>, Option<ErrorCode>)> {
+        errors
+            .iter()
+            .map(|err| (err.offset(), err.code()))
+            .collect()
+    }
+
+    assert_eq!(validate("{ a: [1, 'two', { b: null }], c: .5, }"), []);
+
+    assert_eq!(
+        validate("{ a: tru, b: [1 2], c: 'ok' }"),
+        [err_at(0, 8, ExpectedBool), err_at(0, 16, ExpectedComma)]
+    );
+    assert_eq!(
+        codes(&validate("[1, , 2, : 3 4]")),
+        [
+            (Some(4), Some(ExpectedValue)),
+            (Some(9), Some(ExpectedValue)),
+            (Some(13), Some(ExpectedComma)),
+        ]
+    );
+    assert_eq!(
+        codes(&validate("{ a 1, b: , c: 'd\ne' f: 2 }")),
+        [
+            (Some(4), Some(ExpectedColon)),
+            (Some(10), Some(ExpectedValue)),
+            (Some(17), Some(LineTerminatorInString)),
+            (Some(22), Some(ExpectedComma)),
+        ]
+    );
+
+    // Strings we give up on are skipped entirely.
+    assert_eq!(
+        codes(&validate("['\\u12', ']', 0x]")),
+        [
+            (Some(2), Some(InvalidEscapeSequence)),
+            (Some(16), Some(ExpectedNumber)),
+        ]
+    );
+
+    // Mismatched brackets.
+    assert_eq!(
+        validate("{ a: [1, 2 }"),
+        [err_at(0, 11, ExpectedClosingBracket)]
+    );
+    assert_eq!(validate("[1, 2}]"), [err_at(0, 5, ExpectedClosingBracket)]);
+    // Each unclosed collection is reported.
+    assert_eq!(
+        validate("[1, [2"),
+        [err_at(0, 0, EofParsingArray), err_at(0, 4, EofParsingArray)]
+    );
+    assert_eq!(validate(""), [err_at(0, 0, EofParsingValue)]);
+    assert_eq!(validate("{} {}"), [err_at(0, 3, TrailingCharacters)]);
+
+    // Options are respected.
+    let errors = Deserializer::from_str("{ 'a': 1, a: 2 /* */ }")
+        .with_options(ParseOptions {
+            dialect: Dialect::Json,
+            duplicate_keys: DuplicateKeys::Error,
+            ..ParseOptions::default()
+        })
+        .validate();
+    assert_eq!(
+        codes(&errors),
+        [
+            (Some(2), Some(SingleQuotesInJson)),
+            (Some(10), Some(UnquotedKeyInJson)),
+            (
+                Some(10),
+                Some(DuplicateKey {
+                    first: Position { line: 0, column: 2 }
+                })
+            ),
+            (Some(15), Some(CommentInJson)),
+        ]
+    );
+    let errors = Deserializer::from_str("[[[1, x]]]")
+        .with_options(ParseOptions {
+            max_depth: Some(2),
+            ..ParseOptions::default()
+        })
+        .validate();
+    assert_eq!(errors, [err_at(0, 2, RecursionLimitExceeded)]);
+}
+
+#[test]
+fn display_with_source() {
+    fn diagnostic(source: &str) -> String {
+        from_str::<Vec<u8>>(source)
+            .unwrap_err()
+            .display_with_source(source, None)
+            .to_string()
+    }
+
+    // CRLF, LF, CR, U+2028, and U+2029 each end one line.
+    assert_eq!(
+        diagnostic("[\r\n1,\n2,\r3,\u{2028}4,\u{2029}\t256,\n6,\n7,\n8,\n9,\n10]"),
+        "\
+error: [4]: invalid value: integer `256`, expected u8
+ --> 6:2
+  |
+4 | 3,
+5 | 4,
+6 | \t256,
+  | \t^^^
+7 | 6,
+8 | 7,
+"
+    );
+    assert_eq!(
+        diagnostic("[\n1,\n2,\n3,\n4,\n5,\n6,\n7,\n8,\n9,\nx]"),
+        "\
+error: [9]: expected number
+  --> 11:1
+   |
+ 9 | 8,
+10 | 9,
+11 | x]
+   | ^
+"
+    );
+    assert_eq!(
+        diagnostic("[1, -1]"),
+        "\
+error: [1]: invalid value: integer `-1`, expected u8
+ --> 1:5
+  |
+1 | [1, -1]
+  |     ^^
+"
+    );
+    assert_eq!(
+        diagnostic("[1, 2"),
+        "\
+error: EOF parsing array
+ --> 1:1
+  |
+1 | [1, 2
+  | ^^^^^
+"
+    );
+    assert_eq!(
+        Error::new(InvalidKey)
+            .display_with_source("", Some("config.json5"))
+            .to_string(),
+        "error: invalid key\n --> config.json5\n"
+    );
+}
+
 // "Kitchen-sink example" from https://json5.org/
 #[test]
 fn json5_org_example() {
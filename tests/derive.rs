@@ -0,0 +1,202 @@
+#![cfg(feature = "derive")]
+
+use std::borrow::Cow;
+
+use json5::{Documented, SerializerConfig, WithDocs, default_config, to_string, to_string_with};
+use serde_derive::{Deserialize, Serialize};
+
+/// Settings for the server.
+#[derive(Serialize, Documented)]
+#[serde(rename_all = "camelCase")]
+struct Config {
+    /// Port the HTTP server listens on.
+    #[serde(default = "default_port")]
+    port: u16,
+    /// Hosts to accept requests for.
+    ///
+    /// Leave empty to accept any host.
+    allowed_hosts: Vec<String>,
+    #[serde(rename = "TLS")]
+    tls: Tls,
+    /// Seconds to wait before giving up.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<u32>,
+    #[serde(skip)]
+    cache: Vec<u8>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            port: 0,
+            allowed_hosts: vec!["localhost".to_owned()],
+            tls: Tls::default(),
+            timeout: None,
+            cache: vec![1, 2, 3],
+        }
+    }
+}
+
+fn default_port() -> u16 {
+    8080
+}
+
+#[derive(Default, Serialize, Documented)]
+#[serde(default)]
+struct Tls {
+    /// Whether to serve HTTPS.
+    enabled: bool,
+    #[serde(rename(serialize = "cert-path", deserialize = "cert"))]
+    cert_path: String,
+}
+
+#[test]
+fn documented_default() {
+    let config = Config::documented_default();
+    assert_eq!(config.port, 8080);
+    assert_eq!(config.allowed_hosts, ["localhost"]);
+    assert!(config.cache.is_empty());
+}
+
+#[test]
+fn default_config_is_commented() {
+    assert_eq!(
+        default_config::<Config>(),
+        Ok("{
+  // Port the HTTP server listens on.
+  port: 8080,
+  // Hosts to accept requests for.
+  //
+  // Leave empty to accept any host.
+  allowedHosts: [
+    \"localhost\",
+  ],
+  TLS: {
+    // Whether to serve HTTPS.
+    enabled: false,
+    \"cert-path\": \"\",
+  },
+}"
+        .to_owned())
+    );
+}
+
+#[test]
+fn matches_serialize() {
+    let config = Config {
+        timeout: Some(30),
+        ..Config::default()
+    };
+    let compact = SerializerConfig {
+        compact: true,
+        ..SerializerConfig::default()
+    };
+    assert_eq!(
+        to_string_with(&WithDocs(&config), &compact),
+        to_string_with(&config, &compact)
+    );
+    assert_eq!(
+        serde_json::to_string(&WithDocs(&config)).unwrap(),
+        serde_json::to_string(&config).unwrap()
+    );
+    assert!(to_string(&WithDocs(&config)).unwrap().contains(
+        "  // Seconds to wait before giving up.
+  timeout: 30,"
+    ));
+}
+
+/// A generic wrapper.
+#[derive(Default, Serialize, Documented)]
+#[serde(rename = "Wrapper")]
+struct Generic<T, U> {
+    /// The wrapped value.
+    value: T,
+    #[serde(skip)]
+    unused: Option<U>,
+}
+
+// Only used by a skipped field, so doesn't need to be `Serialize`.
+#[derive(Default)]
+struct NotSerialize;
+
+#[derive(Serialize, Documented)]
+#[serde(bound = "T: serde::Serialize + Default")]
+struct Bounded<T> {
+    #[serde(default)]
+    value: T,
+}
+
+#[test]
+fn generics() {
+    let generic = Generic::<Vec<u8>, NotSerialize> {
+        value: vec![1],
+        unused: None,
+    };
+    assert_eq!(
+        to_string(&WithDocs(&generic)),
+        Ok("{\n  // The wrapped value.\n  value: [\n    1,\n  ],\n}".to_owned())
+    );
+    assert_eq!(
+        default_config::<Generic<Tls, NotSerialize>>().map(|s| s.contains("enabled: false")),
+        Ok(true)
+    );
+    assert!(
+        Generic::<u8, NotSerialize>::documented_default()
+            .unused
+            .is_none()
+    );
+    assert_eq!(
+        to_string(&WithDocs(&Bounded::<u8>::documented_default())),
+        Ok("{\n  value: 0,\n}".to_owned())
+    );
+}
+
+/// Uses every serde attribute the derive knows about that `Config` doesn't.
+#[derive(Default, Serialize, Deserialize, Documented)]
+#[serde(rename = "Every", deny_unknown_fields, default, expecting = "an Every")]
+struct Every<'a> {
+    /// Written, but not read.
+    #[serde(skip_deserializing)]
+    written: u8,
+    /// Read, but not written.
+    #[serde(skip_serializing)]
+    read: u8,
+    /// Read under either name.
+    #[serde(alias = "other_name", deserialize_with = "deserialize_u8")]
+    aliased: u8,
+    #[serde(borrow, rename(deserialize = "b"))]
+    borrowed: Cow<'a, str>,
+}
+
+fn deserialize_u8<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    serde::Deserialize::deserialize(deserializer)
+}
+
+#[test]
+fn every_attribute_matches_serialize() {
+    let every = Every {
+        written: 1,
+        read: 2,
+        aliased: 3,
+        borrowed: Cow::Borrowed("four"),
+    };
+    let compact = SerializerConfig {
+        compact: true,
+        ..SerializerConfig::default()
+    };
+    assert_eq!(
+        to_string_with(&WithDocs(&every), &compact),
+        to_string_with(&every, &compact)
+    );
+    assert_eq!(
+        to_string(&WithDocs(&every)),
+        Ok("{
+  // Written, but not read.
+  written: 1,
+  // Read under either name.
+  aliased: 3,
+  borrowed: \"four\",
+}"
+        .to_owned())
+    );
+}
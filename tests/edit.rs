@@ -0,0 +1,220 @@
+use json5::{Error, ErrorCode, edit::Document};
+
+fn parse(input: &str) -> Document {
+    input.parse().unwrap()
+}
+
+#[test]
+fn get() {
+    let doc = parse("{ servers: [{ port: 80 }, { port: 0x1bb /* https */ }], 'a b': null }");
+    assert_eq!(
+        doc.get("servers.1.port")
+            .map(ToString::to_string)
+            .as_deref(),
+        Some("0x1bb /* https */ ")
+    );
+    assert_eq!(
+        doc.get("a b").map(|v| v.first_token().text.as_str()),
+        Some("null")
+    );
+    assert!(doc.get("").is_some());
+    assert!(doc.get("servers.2").is_none());
+    assert!(doc.get("servers.x").is_none());
+    assert!(doc.get("servers.0.port.nope").is_none());
+
+    // The last occurrence of a duplicate key wins, as when deserializing.
+    let doc = parse("{ a: 1, a: 2 }");
+    assert_eq!(
+        doc.get("a").map(|v| v.first_token().text.as_str()),
+        Some("2")
+    );
+}
+
+#[test]
+fn quoted_paths() {
+    let mut doc = parse("{ 'a.b': 1, 'c[0]': { \"d'\": [2, 3] }, e: [[4]] }");
+    let text = |doc: &Document, path| doc.get(path).map(ToString::to_string);
+    assert_eq!(text(&doc, "['a.b']").as_deref(), Some("1"));
+    assert_eq!(text(&doc, "[\"a.b\"]").as_deref(), Some("1"));
+    assert_eq!(text(&doc, "['c[0]'][\"d'\"][1]").as_deref(), Some("3"));
+    assert_eq!(text(&doc, "['c[0]'].\"d'\".1"), None);
+    assert_eq!(text(&doc, "['c[0]']['d\\''].1").as_deref(), Some("3"));
+    assert_eq!(text(&doc, "e[0][0]").as_deref(), Some("4"));
+    assert_eq!(text(&doc, "e.[0].0").as_deref(), Some("4"));
+    assert_eq!(text(&doc, "a.b"), None);
+
+    // Malformed paths don't refer to anything.
+    for path in ["['a.b'", "['a.b]", "[a]", "[]", "['a.b']x", "e[0"] {
+        assert_eq!(text(&doc, path), None, "{path}");
+    }
+    assert_eq!(
+        doc.set("['a.b'", &1),
+        Err(Error::new(ErrorCode::PathNotFound))
+    );
+
+    doc.set("['a.b']", &5).unwrap();
+    doc.insert("['x.y']", &6).unwrap();
+    doc.rename_key("['c[0]']", "c").unwrap();
+    assert!(doc.remove("c['d\\''][0]").is_some());
+    assert_eq!(
+        doc.to_string(),
+        "{ 'a.b': 5, c: { \"d'\": [3] }, e: [[4]], \"x.y\": 6 }"
+    );
+}
+
+#[test]
+fn set() {
+    let mut doc = parse(
+        "{
+  // The name.
+  name: 'old', // trailing
+  nested: { list: [1, 2, 3] },
+}
+",
+    );
+    doc.set("name", "new").unwrap();
+    doc.set("nested.list.1", &[4, 5]).unwrap();
+    doc.set("nested.extra", &true).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "{
+  // The name.
+  name: \"new\", // trailing
+  nested: { list: [1, [
+    4,
+    5,
+  ], 3], extra: true },
+}
+"
+    );
+
+    let mut doc = parse("/* root */ 1 // end");
+    doc.set("", &[1]).unwrap();
+    assert_eq!(doc.to_string(), "/* root */ [\n  1,\n] // end");
+
+    assert_eq!(doc.set("0.a", &1), Err(Error::new(ErrorCode::PathNotFound)));
+    assert_eq!(doc.set("1", &1), Err(Error::new(ErrorCode::PathNotFound)));
+}
+
+#[test]
+fn insert_multiline() {
+    let mut doc = parse(
+        "{
+    a: 1, // one
+    b: {
+        c: 2
+    },
+}",
+    );
+    doc.insert("d", &[3]).unwrap();
+    doc.insert("b.e", "four").unwrap();
+    doc.insert("b.needs quotes", &5).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "{
+    a: 1, // one
+    b: {
+        c: 2,
+        e: \"four\",
+        \"needs quotes\": 5
+    },
+    d: [
+        3,
+    ],
+}"
+    );
+
+    // New values are indented like the rest of the document.
+    let mut doc = parse("{\n\ta: {\n\t\tb: {},\n\t},\n}");
+    doc.insert("a.c", &[1]).unwrap();
+    doc.insert("a.b.d", &[2]).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "{\n\ta: {\n\t\tb: {\n\t\t\td: [\n\t\t\t\t2,\n\t\t\t],\n\t\t},\n\
+         \t\tc: [\n\t\t\t1,\n\t\t],\n\t},\n}"
+    );
+
+    let mut doc = parse("[\r\n  1,\r\n  3\r\n]");
+    doc.insert("1", &2).unwrap();
+    doc.insert("3", &4).unwrap();
+    doc.insert("0", &0).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "[\r\n  0,\r\n  1,\r\n  2,\r\n  3,\r\n  4\r\n]"
+    );
+}
+
+#[test]
+fn insert_inline() {
+    let mut doc = parse("{ a: [1, 2], b: [1,2], c: [ 1, ], d: {} }");
+    doc.insert("a.2", &3).unwrap();
+    doc.insert("a.0", &0).unwrap();
+    doc.insert("b.2", &3).unwrap();
+    doc.insert("c.1", &2).unwrap();
+    doc.insert("d.x", &1).unwrap();
+    doc.insert("e", &[(); 0]).unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "{ a: [0, 1, 2, 3], b: [1,2,3], c: [ 1, 2, ], d: {
+  x: 1,
+}, e: [] }"
+    );
+
+    assert_eq!(
+        doc.insert("a.5", &5),
+        Err(Error::new(ErrorCode::PathNotFound))
+    );
+    assert_eq!(doc.insert("", &5), Err(Error::new(ErrorCode::PathNotFound)));
+    assert_eq!(
+        doc.insert("nope.a", &5),
+        Err(Error::new(ErrorCode::PathNotFound))
+    );
+}
+
+#[test]
+fn remove() {
+    let mut doc = parse(
+        "{
+  // Goes with a.
+  a: 1,
+  b: [1, 2, 3],
+  c: 3, // stays with b
+  d: 4
+}",
+    );
+    assert_eq!(doc.remove("a").map(|v| v.to_string()).as_deref(), Some("1"));
+    assert!(doc.remove("b.1").is_some());
+    assert!(doc.remove("b.1").is_some());
+    assert!(doc.remove("d").is_some());
+    assert!(doc.remove("d").is_none());
+    assert!(doc.remove("").is_none());
+    assert_eq!(
+        doc.to_string(),
+        "{
+  b: [1],
+  c: 3 // stays with b
+}"
+    );
+}
+
+#[test]
+fn rename_key() {
+    let mut doc = parse("{ /* a */ 'old' /* b */: 1, other: 2 }");
+    doc.rename_key("old", "new").unwrap();
+    doc.rename_key("other", "needs-quotes").unwrap();
+    assert_eq!(
+        doc.to_string(),
+        "{ /* a */ new /* b */: 1, \"needs-quotes\": 2 }"
+    );
+    assert_eq!(
+        doc.rename_key("nope", "x"),
+        Err(Error::new(ErrorCode::PathNotFound))
+    );
+}
+
+#[test]
+fn untouched_round_trip() {
+    let input = include_str!("chromium_example.json5");
+    assert_eq!(parse(input).to_string(), input);
+    assert_eq!(parse(input).as_cst().to_string(), input);
+}
@@ -1,5 +1,11 @@
+use std::collections::HashMap;
+
 use indexmap::IndexMap;
-use json5::{Error, ErrorCode, to_string};
+use json5::{
+    ByteEncoding, Commented, Dialect, Error, ErrorCode, FloatFormat, Indent, IntegerFormat,
+    LineEnding, NonFinite, ParseOptions, Quote, SerializerConfig, TrailingCommas, Value,
+    natural_order, ser::Hex, to_string, to_string_with,
+};
 use serde_bytes::{ByteBuf, Bytes};
 use serde_derive::Serialize;
 
@@ -325,3 +331,982 @@ fn enum_representations() {
         );
     }
 }
+
+#[test]
+fn config() {
+    #[derive(Serialize)]
+    struct S {
+        a: Vec<i32>,
+        b: E,
+        c: Vec<()>,
+    }
+
+    #[derive(Serialize)]
+    enum E {
+        V { x: i32 },
+    }
+
+    let s = S {
+        a: vec![1, 2],
+        b: E::V { x: 0 },
+        c: vec![],
+    };
+
+    assert_eq!(
+        to_string_with(&s, &SerializerConfig::default()),
+        to_string(&s)
+    );
+    assert_eq!(
+        to_string_with(
+            &s,
+            &SerializerConfig {
+                compact: true,
+                ..SerializerConfig::default()
+            }
+        ),
+        Ok("{a:[1,2],b:{V:{x:0}},c:[]}".to_owned())
+    );
+    assert_eq!(
+        to_string_with(
+            &s,
+            &SerializerConfig {
+                indent: Indent::Tabs,
+                line_ending: LineEnding::CrLf,
+                ..SerializerConfig::default()
+            }
+        ),
+        Ok("{\r\n\ta: [\r\n\t\t1,\r\n\t\t2,\r\n\t],\r\n\tb: {\r\n\t\tV: {\r\n\t\t\tx: 0,\r\n\t\t},\r\n\t},\r\n\tc: [],\r\n}".to_owned())
+    );
+    assert_eq!(
+        to_string_with(
+            &[[1]],
+            &SerializerConfig {
+                indent: Indent::Spaces(4),
+                ..SerializerConfig::default()
+            }
+        ),
+        Ok("[\n    [\n        1,\n    ],\n]".to_owned())
+    );
+    assert_eq!(
+        to_string_with(
+            &E::V { x: 0 },
+            &SerializerConfig {
+                indent: Indent::Spaces(0),
+                ..SerializerConfig::default()
+            }
+        ),
+        Ok("{\nV: {\nx: 0,\n},\n}".to_owned())
+    );
+}
+
+#[test]
+fn max_width() {
+    #[derive(Serialize)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(Serialize)]
+    struct S {
+        points: Vec<Point>,
+        tags: Vec<&'static str>,
+        empty: Vec<()>,
+        nested: Vec<Vec<i32>>,
+    }
+
+    #[derive(Serialize)]
+    enum E {
+        V(i32, i32),
+    }
+
+    let s = S {
+        points: vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }],
+        tags: vec!["a", "b"],
+        empty: vec![],
+        nested: vec![vec![1, 2, 3], vec![4, 5, 6]],
+    };
+    let config = |max_width| SerializerConfig {
+        max_width: Some(max_width),
+        ..SerializerConfig::default()
+    };
+
+    assert_eq!(
+        to_string_with(&s, &config(200)),
+        Ok("{ points: [{ x: 1, y: 2 }, { x: 3, y: 4 }], tags: [\"a\", \"b\"], empty: [], nested: [[1, 2, 3], [4, 5, 6]] }".to_owned())
+    );
+    assert_eq!(
+        to_string_with(&s, &config(43)),
+        Ok("{
+  points: [{ x: 1, y: 2 }, { x: 3, y: 4 }],
+  tags: [\"a\", \"b\"],
+  empty: [],
+  nested: [[1, 2, 3], [4, 5, 6]],
+}"
+        .to_owned())
+    );
+    // The trailing comma counts towards the width.
+    assert_eq!(
+        to_string_with(&s, &config(42)),
+        Ok("{
+  points: [
+    { x: 1, y: 2 },
+    { x: 3, y: 4 },
+  ],
+  tags: [\"a\", \"b\"],
+  empty: [],
+  nested: [[1, 2, 3], [4, 5, 6]],
+}"
+        .to_owned())
+    );
+    assert_eq!(
+        to_string_with(&s, &config(20)),
+        Ok("{
+  points: [
+    { x: 1, y: 2 },
+    { x: 3, y: 4 },
+  ],
+  tags: [\"a\", \"b\"],
+  empty: [],
+  nested: [
+    [1, 2, 3],
+    [4, 5, 6],
+  ],
+}"
+        .to_owned())
+    );
+    assert_eq!(to_string_with(&s, &config(0)), to_string(&s));
+
+    assert_eq!(
+        to_string_with(&E::V(1, 2), &config(20)),
+        Ok("{ V: [1, 2] }".to_owned())
+    );
+    assert_eq!(
+        to_string_with(&E::V(1, 2), &config(12)),
+        Ok("{\n  V: [1, 2],\n}".to_owned())
+    );
+    assert_eq!(
+        to_string_with(&[1, 2], &config(5)),
+        Ok("[\n  1,\n  2,\n]".to_owned())
+    );
+    assert_eq!(to_string_with(&[1, 2], &config(6)), Ok("[1, 2]".to_owned()));
+    assert_eq!(to_string_with(&1, &config(0)), Ok("1".to_owned()));
+
+    assert_eq!(
+        to_string_with(
+            &[[1, 2], [3, 4]],
+            &SerializerConfig {
+                indent: Indent::Tabs,
+                ..config(11)
+            }
+        ),
+        Ok("[\n\t[1, 2],\n\t[3, 4],\n]".to_owned())
+    );
+    assert_eq!(
+        to_string_with(
+            &[[1, 2]],
+            &SerializerConfig {
+                indent: Indent::Tabs,
+                ..config(7)
+            }
+        ),
+        Ok("[\n\t[\n\t\t1,\n\t\t2,\n\t],\n]".to_owned())
+    );
+    assert_eq!(
+        to_string_with(
+            &s,
+            &SerializerConfig {
+                compact: true,
+                ..config(20)
+            }
+        ),
+        to_string_with(
+            &s,
+            &SerializerConfig {
+                compact: true,
+                ..SerializerConfig::default()
+            }
+        )
+    );
+}
+
+#[test]
+fn trailing_commas() {
+    let value = json5::json5!({ a: [[1], []] });
+    let config = |trailing_commas, max_width| SerializerConfig {
+        trailing_commas,
+        max_width,
+        ..SerializerConfig::default()
+    };
+
+    assert_eq!(
+        to_string_with(&value, &config(TrailingCommas::Never, None)),
+        Ok("{\n  a: [\n    [\n      1\n    ],\n    []\n  ]\n}".to_owned())
+    );
+    assert_eq!(
+        to_string_with(&value, &config(TrailingCommas::Always, None)),
+        to_string(&value)
+    );
+    assert_eq!(
+        to_string_with(&value, &config(TrailingCommas::Always, Some(80))),
+        Ok("{ a: [[1,], [],], }".to_owned())
+    );
+    assert_eq!(
+        to_string_with(&value, &config(TrailingCommas::Multiline, Some(80))),
+        Ok("{ a: [[1], []] }".to_owned())
+    );
+    assert_eq!(
+        to_string_with(&value, &config(TrailingCommas::Multiline, Some(15))),
+        Ok("{\n  a: [[1], []],\n}".to_owned())
+    );
+    assert_eq!(
+        to_string_with(&value, &config(TrailingCommas::Never, Some(15))),
+        Ok("{\n  a: [[1], []]\n}".to_owned())
+    );
+    // Only the last element has room without a trailing comma.
+    assert_eq!(
+        to_string_with(&[[1, 2], [3, 4]], &config(TrailingCommas::Never, Some(8))),
+        Ok("[\n  [\n    1,\n    2\n  ],\n  [3, 4]\n]".to_owned())
+    );
+    assert_eq!(
+        to_string_with(
+            &[[1, 2], [3, 4]],
+            &config(TrailingCommas::Multiline, Some(8))
+        ),
+        Ok("[\n  [\n    1,\n    2,\n  ],\n  [\n    3,\n    4,\n  ],\n]".to_owned())
+    );
+    assert_eq!(
+        to_string_with(
+            &value,
+            &SerializerConfig {
+                compact: true,
+                ..config(TrailingCommas::Always, None)
+            }
+        ),
+        Ok("{a:[[1,],[],],}".to_owned())
+    );
+}
+
+#[test]
+fn quotes() {
+    let config = |quote, quote_keys| SerializerConfig {
+        quote,
+        quote_keys,
+        compact: true,
+        ..SerializerConfig::default()
+    };
+    let single = config(Quote::Single, false);
+
+    assert_eq!(to_string_with(&"foo", &single), Ok("'foo'".to_owned()));
+    assert_eq!(
+        to_string_with(&"single: '", &single),
+        Ok(r#""single: '""#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(&r#"double: ""#, &single),
+        Ok(r#"'double: "'"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(&r#"double: ", single: '"#, &single),
+        Ok(r#"'double: ", single: \''"#.to_owned())
+    );
+    assert_eq!(to_string_with(&'a', &single), Ok("'a'".to_owned()));
+    assert_eq!(to_string_with(&'\'', &single), Ok(r#""'""#.to_owned()));
+    assert_eq!(
+        to_string_with(&Bytes::new(b"JSON5"), &single),
+        Ok("'4a534f4e35'".to_owned())
+    );
+
+    let mut map = IndexMap::new();
+    map.insert("a", 1);
+    map.insert("b c", 2);
+    assert_eq!(
+        to_string_with(&map, &single),
+        Ok("{a:1,'b c':2}".to_owned())
+    );
+    assert_eq!(
+        to_string_with(&map, &config(Quote::Double, true)),
+        Ok(r#"{"a":1,"b c":2}"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(&map, &config(Quote::Single, true)),
+        Ok("{'a':1,'b c':2}".to_owned())
+    );
+    assert_eq!(
+        to_string_with(&IndexMap::from([(1, 'x')]), &single),
+        Ok("{'1':'x'}".to_owned())
+    );
+    assert_eq!(
+        to_string_with(
+            &IndexMap::from([(true, 'x'), (false, 'y')]),
+            &config(Quote::Double, true)
+        ),
+        Ok(r#"{"true":"x","false":"y"}"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(&IndexMap::from([('x', 1)]), &config(Quote::Double, true)),
+        Ok(r#"{"x":1}"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(&IndexMap::from([((), 1)]), &config(Quote::Single, true)),
+        Ok("{'null':1}".to_owned())
+    );
+    assert_eq!(
+        to_string_with(&IndexMap::from([(Some(1), 'x')]), &single),
+        Ok("{'1':'x'}".to_owned())
+    );
+}
+
+#[test]
+fn json_dialect() {
+    #[derive(Serialize)]
+    struct S {
+        name: &'static str,
+        chars: Vec<char>,
+        list: Vec<f64>,
+        map: IndexMap<i32, bool>,
+        empty: Vec<()>,
+        e: E,
+    }
+
+    #[derive(Serialize)]
+    enum E {
+        V(i32),
+    }
+
+    // An object with a float key.
+    struct FloatKey(f64);
+
+    impl serde::Serialize for FloatKey {
+        fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+            s.collect_map([(self.0, 1)])
+        }
+    }
+
+    let json = |non_finite| SerializerConfig {
+        dialect: Dialect::Json,
+        non_finite,
+        // These are all overridden by the JSON dialect.
+        quote: Quote::Single,
+        quote_keys: false,
+        trailing_commas: TrailingCommas::Always,
+        ..SerializerConfig::default()
+    };
+
+    let s = S {
+        name: "it's \"json\"\t\u{0}\u{1f}\u{8}\u{c}\n\u{2028}",
+        chars: vec!['\'', '"', '\u{7}'],
+        list: vec![1.5, -0.0, 1e21],
+        map: IndexMap::from([(1, true)]),
+        empty: vec![],
+        e: E::V(1),
+    };
+    let output = to_string_with(&s, &json(NonFinite::Error)).unwrap();
+    assert_eq!(
+        output,
+        r#"{
+  "name": "it's \"json\"\t\u0000\u001f\b\f\n\u2028",
+  "chars": [
+    "'",
+    "\"",
+    "\u0007"
+  ],
+  "list": [
+    1.5,
+    -0,
+    1000000000000000000000
+  ],
+  "map": {
+    "1": true
+  },
+  "empty": [],
+  "e": {
+    "V": 1
+  }
+}"#
+    );
+    assert!(serde_json::from_str::<serde_json::Value>(&output).is_ok());
+
+    let non_finite = [f64::NAN, f64::INFINITY, -f64::INFINITY];
+    assert_eq!(
+        to_string_with(&f64::NAN, &json(NonFinite::Error)),
+        Err(Error::new(ErrorCode::NanInJson))
+    );
+    assert_eq!(
+        to_string_with(&-f32::INFINITY, &json(NonFinite::Error)),
+        Err(Error::new(ErrorCode::InfinityInJson))
+    );
+    assert_eq!(
+        to_string_with(
+            &non_finite,
+            &SerializerConfig {
+                compact: true,
+                ..json(NonFinite::Null)
+            }
+        ),
+        Ok("[null,null,null]".to_owned())
+    );
+    assert_eq!(
+        to_string_with(
+            &non_finite.map(|f| -f),
+            &SerializerConfig {
+                compact: true,
+                ..json(NonFinite::String)
+            }
+        ),
+        Ok(r#"["NaN","-Infinity","Infinity"]"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(
+            &FloatKey(f64::INFINITY),
+            &SerializerConfig {
+                compact: true,
+                ..json(NonFinite::String)
+            }
+        ),
+        Ok(r#"{"Infinity":1}"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(
+            &FloatKey(f64::NAN),
+            &SerializerConfig {
+                compact: true,
+                ..json(NonFinite::Null)
+            }
+        ),
+        Ok(r#"{"null":1}"#.to_owned())
+    );
+
+    // Keys of every type are quoted.
+    let compact = SerializerConfig {
+        compact: true,
+        ..json(NonFinite::Error)
+    };
+    assert_eq!(
+        to_string_with(&HashMap::from([(Some(7u8), 1)]), &compact),
+        Ok(r#"{"7":1}"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(&HashMap::from([(Some("a"), 1)]), &compact),
+        Ok(r#"{"a":1}"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(&HashMap::from([((), 1)]), &compact),
+        Ok(r#"{"null":1}"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(&HashMap::from([(true, 1)]), &compact),
+        Ok(r#"{"true":1}"#.to_owned())
+    );
+
+    // The policy doesn't apply to JSON5.
+    assert_eq!(
+        to_string_with(
+            &non_finite,
+            &SerializerConfig {
+                compact: true,
+                non_finite: NonFinite::Null,
+                ..SerializerConfig::default()
+            }
+        ),
+        Ok("[NaN,Infinity,-Infinity]".to_owned())
+    );
+}
+
+#[test]
+fn ascii_only() {
+    let config = SerializerConfig {
+        ascii_only: true,
+        compact: true,
+        ..SerializerConfig::default()
+    };
+
+    let s = "café 🦀 \t\u{7f}\u{0} \u{2028} 'ok'";
+    let output = to_string_with(&s, &config).unwrap();
+    assert_eq!(
+        output,
+        r#""caf\u00e9 \ud83e\udd80 \t\u007f\u0000 \u2028 'ok'""#
+    );
+    assert!(output.is_ascii());
+    assert_eq!(json5::from_str::<String>(&output).as_deref(), Ok(s));
+
+    assert_eq!(to_string_with(&'é', &config), Ok(r#""\u00e9""#.to_owned()));
+    assert_eq!(
+        to_string_with(&'🦀', &config),
+        Ok(r#""\ud83e\udd80""#.to_owned())
+    );
+
+    // Keys which would need escaping are quoted instead.
+    let map = IndexMap::from([("τ", 1), ("tau", 2), ("a\u{7f}", 3)]);
+    assert_eq!(
+        to_string_with(&map, &config),
+        Ok(r#"{"\u03c4":1,tau:2,"a\u007f":3}"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(&IndexMap::from([('τ', 1)]), &config),
+        Ok(r#"{"\u03c4":1}"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(
+            &map,
+            &SerializerConfig {
+                compact: true,
+                ..SerializerConfig::default()
+            }
+        ),
+        Ok("{τ:1,tau:2,\"a\u{7f}\":3}".to_owned())
+    );
+
+    // Comments are escaped too, whether or not they're laid out to fit a width.
+    let value = vec![Commented::new(1, "héllo 🦀")];
+    let mut config = SerializerConfig {
+        ascii_only: true,
+        ..SerializerConfig::default()
+    };
+    let expected = "[\n  // h\\u00e9llo \\ud83e\\udd80\n  1,\n]";
+    assert_eq!(to_string_with(&value, &config).as_deref(), Ok(expected));
+    config.max_width = Some(80);
+    assert_eq!(to_string_with(&value, &config).as_deref(), Ok(expected));
+}
+
+#[test]
+fn sort_keys() {
+    #[derive(Serialize)]
+    struct S {
+        z: HashMap<&'static str, i32>,
+        a: E,
+    }
+
+    #[derive(Serialize)]
+    enum E {
+        V { y: i32, x: i32 },
+    }
+
+    let s = S {
+        z: HashMap::from([
+            ("item10", 1),
+            ("item2", 2),
+            ("b c", 3),
+            ("'a'", 4),
+            ("c", 5),
+        ]),
+        a: E::V { y: 0, x: 0 },
+    };
+    let config = |sort_keys, sort_struct_fields| SerializerConfig {
+        sort_keys,
+        sort_struct_fields,
+        compact: true,
+        ..SerializerConfig::default()
+    };
+
+    // Quoted keys are compared after unescaping.
+    assert_eq!(
+        to_string_with(&s, &config(Some(str::cmp), false)),
+        Ok(r#"{z:{"'a'":4,"b c":3,c:5,item10:1,item2:2},a:{V:{y:0,x:0}}}"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(&s, &config(Some(natural_order), false)),
+        Ok(r#"{z:{"'a'":4,"b c":3,c:5,item2:2,item10:1},a:{V:{y:0,x:0}}}"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(&s, &config(Some(natural_order), true)),
+        Ok(r#"{a:{V:{x:0,y:0}},z:{"'a'":4,"b c":3,c:5,item2:2,item10:1}}"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(&s, &config(Some(|a, b| b.cmp(a)), true)),
+        Ok(r#"{z:{item2:2,item10:1,c:5,"b c":3,"'a'":4},a:{V:{y:0,x:0}}}"#.to_owned())
+    );
+    // `sort_struct_fields` does nothing without `sort_keys`.
+    assert_eq!(
+        to_string_with(&E::V { y: 0, x: 0 }, &config(None, true)),
+        Ok("{V:{y:0,x:0}}".to_owned())
+    );
+
+    // Non-string keys are compared as they'd be written.
+    assert_eq!(
+        to_string_with(
+            &HashMap::from([(10, 'a'), (9, 'b'), (100, 'c')]),
+            &config(Some(natural_order), false)
+        ),
+        Ok(r#"{"9":"b","10":"a","100":"c"}"#.to_owned())
+    );
+
+    let pretty = SerializerConfig {
+        sort_keys: Some(str::cmp),
+        ..SerializerConfig::default()
+    };
+    let map = HashMap::from([("b", vec![2]), ("a", vec![]), ("c", vec![3, 4])]);
+    assert_eq!(
+        to_string_with(&map, &pretty),
+        Ok("{\n  a: [],\n  b: [\n    2,\n  ],\n  c: [\n    3,\n    4,\n  ],\n}".to_owned())
+    );
+    assert_eq!(
+        to_string_with(
+            &map,
+            &SerializerConfig {
+                max_width: Some(30),
+                ..pretty
+            }
+        ),
+        Ok("{ a: [], b: [2], c: [3, 4] }".to_owned())
+    );
+    assert_eq!(
+        to_string_with(
+            &map,
+            &SerializerConfig {
+                trailing_commas: TrailingCommas::Always,
+                ..config(Some(str::cmp), false)
+            }
+        ),
+        Ok("{a:[],b:[2,],c:[3,4,],}".to_owned())
+    );
+}
+
+#[test]
+fn natural_ordering() {
+    use std::cmp::Ordering::*;
+
+    for (a, b, ordering) in [
+        ("", "", Equal),
+        ("", "a", Less),
+        ("a", "", Greater),
+        ("a2", "a10", Less),
+        ("a10", "a10", Equal),
+        ("a010", "a10", Greater),
+        ("a10b", "a10c", Less),
+        ("a99", "a100", Less),
+        ("1.5", "1.10", Less),
+        ("a", "B", Greater),
+        ("x2y3", "x2y20", Less),
+        ("é1", "é01", Less),
+        ("99999999999999999999999", "100000000000000000000000", Less),
+    ] {
+        assert_eq!(natural_order(a, b), ordering, "{a:?} vs {b:?}");
+        assert_eq!(natural_order(b, a), ordering.reverse(), "{b:?} vs {a:?}");
+    }
+}
+
+#[test]
+fn commented() {
+    #[derive(Serialize)]
+    struct Config {
+        port: Commented<u16>,
+        hosts: Vec<Commented<&'static str>>,
+        tls: Commented<Tls>,
+        mode: Mode,
+    }
+
+    #[derive(Serialize)]
+    struct Tls {
+        enabled: bool,
+    }
+
+    #[derive(Serialize)]
+    enum Mode {
+        Fast(Commented<u8>),
+    }
+
+    let config = Config {
+        port: Commented::new(8080, "Port the HTTP server listens on"),
+        hosts: vec![
+            Commented::new("localhost", "For testing.\r\nRemove me!"),
+            Commented::new("example.com", ""),
+        ],
+        tls: Commented::new(Tls { enabled: true }, "TLS settings"),
+        mode: Mode::Fast(Commented::new(3, "Speed")),
+    };
+    let expected = "{
+  // Port the HTTP server listens on
+  port: 8080,
+  hosts: [
+    // For testing.
+    // Remove me!
+    \"localhost\",
+    \"example.com\",
+  ],
+  // TLS settings
+  tls: {
+    enabled: true,
+  },
+  mode: {
+    // Speed
+    Fast: 3,
+  },
+}";
+    assert_eq!(to_string(&config), Ok(expected.to_owned()));
+
+    // Arrays and objects with comments in can't go on one line.
+    assert_eq!(
+        to_string_with(
+            &config,
+            &SerializerConfig {
+                max_width: Some(1000),
+                ..SerializerConfig::default()
+            }
+        ),
+        Ok("{
+  // Port the HTTP server listens on
+  port: 8080,
+  hosts: [
+    // For testing.
+    // Remove me!
+    \"localhost\",
+    \"example.com\",
+  ],
+  // TLS settings
+  tls: { enabled: true },
+  mode: {
+    // Speed
+    Fast: 3,
+  },
+}"
+        .to_owned())
+    );
+    assert_eq!(
+        to_string_with(
+            &config,
+            &SerializerConfig {
+                sort_keys: Some(str::cmp),
+                sort_struct_fields: true,
+                indent: Indent::Tabs,
+                ..SerializerConfig::default()
+            }
+        ),
+        Ok("{
+\thosts: [
+\t\t// For testing.
+\t\t// Remove me!
+\t\t\"localhost\",
+\t\t\"example.com\",
+\t],
+\tmode: {
+\t\t// Speed
+\t\tFast: 3,
+\t},
+\t// Port the HTTP server listens on
+\tport: 8080,
+\t// TLS settings
+\ttls: {
+\t\tenabled: true,
+\t},
+}"
+        .to_owned())
+    );
+
+    // Comments are left out where they can't go.
+    assert_eq!(
+        to_string_with(
+            &config,
+            &SerializerConfig {
+                compact: true,
+                ..SerializerConfig::default()
+            }
+        ),
+        Ok(
+            r#"{port:8080,hosts:["localhost","example.com"],tls:{enabled:true},mode:{Fast:3}}"#
+                .to_owned()
+        )
+    );
+    assert_eq!(
+        to_string_with(
+            &config,
+            &SerializerConfig {
+                dialect: Dialect::Json,
+                compact: true,
+                ..SerializerConfig::default()
+            }
+        ),
+        serde_json::to_string(&config).map_err(|_| unreachable!())
+    );
+
+    assert_eq!(
+        to_string(&Commented::new([1], "At the top")),
+        Ok("// At the top\n[\n  1,\n]".to_owned())
+    );
+    assert_eq!(
+        json5::to_value(&Commented::new(1, "Ignored")),
+        Ok(json5::json5!(1))
+    );
+    assert_eq!(
+        json5::from_str::<Commented<Vec<u8>>>("// Not kept\n[1]"),
+        Ok(Commented::new(vec![1], ""))
+    );
+}
+
+#[test]
+fn commented_in_other_serializers() {
+    // Other serializers see just the value.
+    assert_eq!(
+        serde_json::to_string(&vec![Commented::new(1, "one")]).unwrap(),
+        "[1]"
+    );
+
+    // If another serializer gives up part way through, the comment doesn't stick around to be
+    // picked up by the next thing to pass through (as a buffering adapter might pass on the
+    // newtype struct `Commented` serializes as).
+    struct Panics;
+
+    impl serde::Serialize for Panics {
+        fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+            std::panic::resume_unwind(Box::new(()))
+        }
+    }
+
+    struct Replayed;
+
+    impl serde::Serialize for Replayed {
+        fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+            s.serialize_newtype_struct("$json5::private::Commented", &1)
+        }
+    }
+
+    let result =
+        std::panic::catch_unwind(|| serde_json::to_string(&Commented::new(Panics, "stale")));
+    assert!(result.is_err());
+    assert_eq!(to_string(&Replayed), Ok("1".to_owned()));
+}
+
+#[test]
+fn integer_format() {
+    let hex = |integers| SerializerConfig {
+        integers,
+        compact: true,
+        ..SerializerConfig::default()
+    };
+    let value = (255u8, -31i32, i128::MIN, 0u64);
+    assert_eq!(
+        to_string_with(&value, &hex(IntegerFormat::UpperHex)),
+        Ok("[0xFF,-0x1F,-0x80000000000000000000000000000000,0x0]".to_owned())
+    );
+    assert_eq!(
+        to_string_with(&value, &hex(IntegerFormat::LowerHex)),
+        Ok("[0xff,-0x1f,-0x80000000000000000000000000000000,0x0]".to_owned())
+    );
+    assert_eq!(
+        json5::from_str::<(u8, i32, i128, u64)>(
+            &to_string_with(&value, &hex(IntegerFormat::UpperHex)).unwrap()
+        ),
+        Ok(value)
+    );
+
+    // Keys stay decimal, and floats are unaffected.
+    assert_eq!(
+        to_string_with(&HashMap::from([(10, 1.5)]), &hex(IntegerFormat::UpperHex)),
+        Ok(r#"{"10":1.5}"#.to_owned())
+    );
+
+    // `Hex` applies to everything inside it, in the case asked for.
+    let value = (Hex(0xdeadbeefu32), Hex([-1i8, 10]), 10);
+    assert_eq!(
+        to_string_with(&value, &hex(IntegerFormat::Decimal)),
+        Ok("[0xDEADBEEF,[-0x1,0xA],10]".to_owned())
+    );
+    assert_eq!(
+        to_string_with(&value, &hex(IntegerFormat::LowerHex)),
+        Ok("[0xdeadbeef,[-0x1,0xa],0xa]".to_owned())
+    );
+    assert_eq!(
+        json5::from_str::<(Hex<u32>, Hex<[i8; 2]>, u8)>("[0xDEADBEEF, [-1, 0xa], 10]"),
+        Ok(value)
+    );
+
+    // JSON has no hex numbers.
+    let json = SerializerConfig {
+        dialect: Dialect::Json,
+        ..hex(IntegerFormat::UpperHex)
+    };
+    assert_eq!(
+        to_string_with(&value, &json),
+        Ok("[3735928559,[-1,10],10]".to_owned())
+    );
+}
+
+#[test]
+fn float_format() {
+    let floats = |floats, float_point| SerializerConfig {
+        floats,
+        float_point,
+        compact: true,
+        ..SerializerConfig::default()
+    };
+    let value = (1.0, 0.1, -0.0, 1e21, 2.5f32, f64::NAN);
+    assert_eq!(
+        to_string_with(&value, &floats(FloatFormat::Shortest, false)),
+        Ok("[1,0.1,-0,1000000000000000000000,2.5,NaN]".to_owned())
+    );
+    assert_eq!(
+        to_string_with(&value, &floats(FloatFormat::Shortest, true)),
+        Ok("[1.0,0.1,-0.0,1000000000000000000000.0,2.5,NaN]".to_owned())
+    );
+    assert_eq!(
+        to_string_with(&value, &floats(FloatFormat::Fixed(2), false)),
+        Ok("[1.00,0.10,-0.00,1000000000000000000000.00,2.50,NaN]".to_owned())
+    );
+    assert_eq!(
+        to_string_with(&value, &floats(FloatFormat::Fixed(0), true)),
+        Ok("[1.0,0.0,-0.0,1000000000000000000000.0,2.0,NaN]".to_owned())
+    );
+
+    // With a decimal point, whole floats are read back as floats.
+    let s = to_string_with(&[1.0, 2.0], &floats(FloatFormat::Shortest, true)).unwrap();
+    let value: Value = s.parse().unwrap();
+    assert!(
+        value
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|v| v.as_number().is_some_and(json5::Number::is_f64))
+    );
+}
+
+#[test]
+fn byte_encodings() {
+    let bytes = |bytes| SerializerConfig {
+        bytes,
+        compact: true,
+        ..SerializerConfig::default()
+    };
+    let value = (
+        Bytes::new(b"JSON5"),
+        Bytes::new(&[0xfb, 0xff]),
+        Bytes::new(b""),
+        HashMap::from([(Bytes::new(b"J"), 1)]),
+    );
+    assert_eq!(
+        to_string_with(&value, &bytes(ByteEncoding::Hex)),
+        Ok(r#"["4a534f4e35","fbff","",{"4a":1}]"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(&value, &bytes(ByteEncoding::Base64)),
+        Ok(r#"["SlNPTjU=","+/8=","",{"Sg==":1}]"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(&value, &bytes(ByteEncoding::Base64UrlSafe)),
+        Ok(r#"["SlNPTjU","-_8","",{"Sg":1}]"#.to_owned())
+    );
+    assert_eq!(
+        to_string_with(&value, &bytes(ByteEncoding::Array)),
+        Ok(r#"[[74,83,79,78,53],[251,255],[],{"4a":1}]"#.to_owned())
+    );
+
+    // Everything reads back with the same encoding, with or without detecting arrays.
+    for encoding in [
+        ByteEncoding::Hex,
+        ByteEncoding::Base64,
+        ByteEncoding::Base64UrlSafe,
+        ByteEncoding::Array,
+    ] {
+        for len in 0..=6 {
+            let value = ByteBuf::from((0..len).map(|b| b * 51).collect::<Vec<u8>>());
+            let s = to_string_with(&value, &bytes(encoding)).unwrap();
+            for detect_bytes in [false, true] {
+                let mut de = json5::Deserializer::from_str(&s).with_options(ParseOptions {
+                    bytes: encoding,
+                    detect_bytes,
+                    ..ParseOptions::default()
+                });
+                assert_eq!(serde::Deserialize::deserialize(&mut de), Ok(value.clone()));
+            }
+        }
+    }
+}
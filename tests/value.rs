@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+
+use json5::{
+    ByteEncoding, Error, ErrorCode, Map, Number, ParseOptions, Position, SerializerConfig, Value,
+    from_str, from_value, from_value_with, json5, to_string, to_string_with, to_value,
+    to_value_with,
+};
+use serde::Deserialize as _;
+use serde_bytes::{ByteBuf, Bytes};
+use serde_derive::{Deserialize, Serialize};
+
+#[test]
+fn parse_value() {
+    assert_eq!(from_str("null"), Ok(Value::Null));
+    assert_eq!(from_str("true"), Ok(Value::Bool(true)));
+    assert_eq!(from_str("'foo'"), Ok(Value::String("foo".to_owned())));
+    assert_eq!(from_str("42"), Ok(Value::from(42)));
+    assert_eq!(from_str("-42"), Ok(Value::from(-42)));
+    assert_eq!(from_str("0x2a"), Ok(Value::from(42u8)));
+    assert_eq!(from_str("4.2"), Ok(Value::from(4.2)));
+    assert_eq!(from_str("-Infinity"), Ok(Value::from(-f64::INFINITY)));
+    assert_eq!(
+        from_str("[1, 'two', [3]]"),
+        Ok(Value::Array(vec![
+            Value::from(1),
+            Value::from("two"),
+            Value::Array(vec![Value::from(3)]),
+        ]))
+    );
+    assert_eq!(
+        from_str("{ a: 1, 'b c': null }"),
+        Ok(Value::Object(Map::from([
+            ("a".to_owned(), Value::from(1)),
+            ("b c".to_owned(), Value::Null),
+        ])))
+    );
+    assert!(from_str::<Value>("NaN").is_ok_and(|v| {
+        v.as_f64()
+            .is_some_and(|f| f.is_nan() && f.is_sign_positive())
+    }));
+}
+
+#[test]
+fn number() {
+    let max: Value = from_str("0xffffffffffffffffffffffffffffffff").unwrap();
+    assert_eq!(max.as_number().and_then(Number::as_u128), Some(u128::MAX));
+    assert_eq!(max.as_u64(), None);
+
+    let min: Value = from_str("-170141183460469231731687303715884105728").unwrap();
+    assert_eq!(min.as_number().and_then(Number::as_i128), Some(i128::MIN));
+    assert_eq!(min.as_i64(), None);
+
+    assert_eq!(Number::from(7u8), Number::from(7i64));
+    assert_eq!(Number::from(-7i8).as_i64(), Some(-7));
+    assert_eq!(Number::from(-7i8).as_u64(), None);
+    assert_eq!(Number::from(7u8).as_f64(), 7.);
+    assert!(Number::from(7u8).is_integer());
+    assert!(Number::from(7.).is_f64());
+    assert_eq!(Number::from(f64::NAN).to_string(), "NaN");
+    assert_eq!(Number::from(-f64::INFINITY).to_string(), "-Infinity");
+}
+
+#[test]
+fn serialize_value() {
+    assert_eq!(to_string(&Value::Null), Ok("null".to_owned()));
+    assert_eq!(
+        to_string(&Value::from(u128::MAX)),
+        Ok(u128::MAX.to_string())
+    );
+    assert_eq!(
+        to_string(&Value::from(i128::MIN)),
+        Ok(i128::MIN.to_string())
+    );
+    assert_eq!(
+        to_string(&Value::from_iter([
+            ("a", Value::from(1)),
+            ("b c", Value::from("d"))
+        ])),
+        Ok("{\n  a: 1,\n  \"b c\": \"d\",\n}".to_owned())
+    );
+    assert_eq!(
+        to_string(&Value::from(vec![Value::Bool(true), Value::Null])),
+        Ok("[\n  true,\n  null,\n]".to_owned())
+    );
+}
+
+#[test]
+fn display_and_from_str() {
+    let value: Value = "{ foo: [1, 0x2, 'three'] }".parse().unwrap();
+    assert_eq!(
+        value.to_string(),
+        "{\n  foo: [\n    1,\n    2,\n    \"three\",\n  ],\n}"
+    );
+    assert_eq!(value.to_string().parse(), Ok(value));
+    assert!("{ foo: }".parse::<Value>().is_err());
+}
+
+#[test]
+fn index() {
+    let mut value: Value = from_str("{ foo: [1, { bar: true }] }").unwrap();
+
+    assert_eq!(value["foo"][0], Value::from(1));
+    assert_eq!(value["foo"][1]["bar"], Value::Bool(true));
+    assert_eq!(value["foo"][2], Value::Null);
+    assert_eq!(value["nope"]["still nope"], Value::Null);
+    assert_eq!(value[0], Value::Null);
+
+    value["foo"][0] = Value::from("one");
+    value["baz"]["qux"] = Value::from(2);
+    assert_eq!(
+        value,
+        from_str::<Value>("{ foo: ['one', { bar: true }], baz: { qux: 2 } }").unwrap()
+    );
+}
+
+#[test]
+#[should_panic(expected = "cannot access index 2 of JSON5 array of length 2")]
+fn index_out_of_bounds() {
+    let mut value: Value = from_str("[1, 2]").unwrap();
+    value[2] = Value::Null;
+}
+
+#[test]
+#[should_panic(expected = "cannot access key \"foo\" of JSON5 number")]
+fn index_non_object() {
+    let mut value: Value = from_str("1").unwrap();
+    value["foo"] = Value::Null;
+}
+
+#[test]
+fn to_value_and_from_value() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config {
+        name: String,
+        port: u16,
+        tags: Vec<String>,
+        limits: HashMap<u32, f64>,
+        mode: Mode,
+        fallback: Option<Box<Config>>,
+        #[serde(with = "serde_bytes")]
+        key: Vec<u8>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Mode {
+        Fast,
+        Slow(u32),
+        Custom { a: i8, b: i8 },
+        Pair(bool, bool),
+    }
+
+    let config = Config {
+        name: "server".to_owned(),
+        port: 8080,
+        tags: vec!["a".to_owned(), "b".to_owned()],
+        limits: HashMap::from([(1, 0.5), (2, f64::INFINITY)]),
+        mode: Mode::Custom { a: -1, b: 1 },
+        fallback: Some(Box::new(Config {
+            name: "backup".to_owned(),
+            port: 8081,
+            tags: vec![],
+            limits: HashMap::new(),
+            mode: Mode::Slow(3),
+            fallback: None,
+            key: vec![],
+        })),
+        key: vec![0xde, 0xad],
+    };
+
+    let value = to_value(&config).unwrap();
+    assert_eq!(value["name"], Value::from("server"));
+    assert_eq!(value["limits"]["2"], Value::from(f64::INFINITY));
+    assert_eq!(value["mode"]["Custom"]["a"], Value::from(-1));
+    assert_eq!(value["fallback"]["mode"]["Slow"], Value::from(3));
+    assert_eq!(value["fallback"]["fallback"], Value::Null);
+    assert_eq!(value["key"], Value::from("dead"));
+    assert_eq!(from_value::<Config>(value.clone()), Ok(config));
+    assert_eq!(Config::deserialize(&value).map(|c| c.port), Ok(8080));
+
+    assert_eq!(from_value(Value::from("Fast")), Ok(Mode::Fast));
+    assert_eq!(
+        from_value(from_str("{ Pair: [true, false] }").unwrap()),
+        Ok(Mode::Pair(true, false))
+    );
+
+    // A value converts to itself.
+    let value: Value = from_str("{ a: [1, 'two', -3.5], b: { c: null } }").unwrap();
+    assert_eq!(to_value(&value).as_ref(), Ok(&value));
+    assert_eq!(from_value(value.clone()), Ok(value));
+}
+
+#[test]
+fn byte_encodings() {
+    let value = (
+        ByteBuf::from("JSON5"),
+        HashMap::from([(ByteBuf::from([0xfb, 0xff]), ByteBuf::new())]),
+    );
+    for bytes in [
+        ByteEncoding::Hex,
+        ByteEncoding::Base64,
+        ByteEncoding::Base64UrlSafe,
+        ByteEncoding::Array,
+    ] {
+        let config = SerializerConfig {
+            bytes,
+            ..SerializerConfig::default()
+        };
+        let options = ParseOptions {
+            bytes,
+            ..ParseOptions::default()
+        };
+        // The same as going through a string.
+        let v = to_value_with(&value, &config).unwrap();
+        assert_eq!(
+            from_str(&to_string_with(&value, &config).unwrap()),
+            Ok(v.clone())
+        );
+        assert_eq!(from_value_with(v, &options), Ok(value.clone()));
+    }
+
+    // Bytes from another deserializer are read as `to_value` would write them.
+    let bytes = serde::de::value::BytesDeserializer::<serde::de::value::Error>::new(b"JSON5");
+    assert_eq!(
+        Value::deserialize(bytes).ok(),
+        to_value(Bytes::new(b"JSON5")).ok()
+    );
+    assert_eq!(
+        from_value(Value::deserialize(bytes).unwrap()),
+        Ok(ByteBuf::from("JSON5"))
+    );
+}
+
+#[test]
+fn to_value_and_from_value_errors() {
+    assert_eq!(
+        to_value(&HashMap::from([(vec![1], 2)])),
+        Err(Error::new(ErrorCode::InvalidKey))
+    );
+    assert_eq!(
+        from_value::<u8>(Value::from(256)),
+        Err(Error::custom("invalid value: integer `256`, expected u8"))
+    );
+    assert_eq!(
+        from_value::<String>(Value::from(vec![1])),
+        Err(Error::custom("invalid type: sequence, expected a string"))
+    );
+    assert_eq!(
+        from_value::<ByteBuf>(Value::from("abc")),
+        Err(Error::new(ErrorCode::InvalidBytes))
+    );
+    assert_eq!(
+        from_value::<HashMap<u8, bool>>(from_str("{ x: true }").unwrap()),
+        Err(Error::new_at(
+            Position { line: 0, column: 0 },
+            ErrorCode::ExpectedNumber
+        ))
+    );
+    assert_eq!(
+        from_value::<Option<u8>>(Value::from(vec![1])),
+        Err(Error::custom("invalid type: sequence, expected u8"))
+    );
+
+    #[derive(Debug, Deserialize)]
+    enum E {
+        A,
+    }
+    assert_eq!(
+        from_value::<E>(Value::from(1)).unwrap_err(),
+        Error::new(ErrorCode::ExpectedStringOrObject)
+    );
+}
+
+#[test]
+fn json5_macro() {
+    let port = 8080;
+    let tags = vec!["a", "b"];
+    let value = json5!({
+        name: "server",
+        'x': 0xff,
+        "quoted key": [Infinity, -Infinity, +1, .5, -.25, +.5e1, null, true, false,],
+        r#type: 'c',
+        port: port + 1,
+        (format!("key{}", 1)): tags,
+        nested: { a: [], b: {}, c: [[1], { d: -1 }] },
+    });
+
+    assert_eq!(
+        value,
+        from_str(
+            "{
+                name: 'server',
+                x: 255,
+                'quoted key': [Infinity, -Infinity, 1, 0.5, -0.25, 5.0, null, true, false],
+                type: 'c',
+                port: 8081,
+                key1: ['a', 'b'],
+                nested: { a: [], b: {}, c: [[1], { d: -1 }] },
+            }"
+        )
+        .unwrap()
+    );
+
+    assert_eq!(json5!(null), Value::Null);
+    assert_eq!(json5!(-.5), Value::from(-0.5));
+    assert_eq!(json5!(-1), Value::from(-1));
+    assert_eq!(json5!(-port), Value::from(-8080));
+    assert_eq!(
+        json5!([-1, -port, --1, -port + 1, 1 + 1, 0xff_u8]),
+        Value::from_iter([-1, -8080, 1, -8079, 2, 255])
+    );
+    assert_eq!(
+        json5!({ a: -1, b: -port, c: -port + 1, d: 2 }),
+        Value::from_iter([("a", -1), ("b", -8080), ("c", -8079), ("d", 2)])
+    );
+    assert_eq!(json5!([1, 2]), Value::from(vec![1, 2]));
+    assert_eq!(json5!({ a: 1 }), Value::from_iter([("a", 1)]));
+    assert!(
+        json5!([-NaN])[0]
+            .as_f64()
+            .is_some_and(|f| f.is_nan() && f.is_sign_negative())
+    );
+}
+
+#[test]
+#[cfg(feature = "preserve_order")]
+fn preserve_order() {
+    let value: Value = from_str("{ b: 1, a: { z: 2, y: 3 }, c: 4 }").unwrap();
+    assert_eq!(
+        to_string(&value),
+        Ok("{\n  b: 1,\n  a: {\n    z: 2,\n    y: 3,\n  },\n  c: 4,\n}".to_owned())
+    );
+
+    let mut value = json5!({ z: 1, y: 2 });
+    value["x"] = Value::from(3);
+    assert_eq!(
+        value
+            .as_object()
+            .map(|o| o.keys().map(String::as_str).collect::<Vec<_>>()),
+        Some(vec!["z", "y", "x"])
+    );
+
+    #[derive(Serialize)]
+    struct S {
+        b: u8,
+        a: u8,
+    }
+    assert_eq!(
+        to_value(&S { b: 1, a: 2 }).map(|v| v.to_string()),
+        Ok("{\n  b: 1,\n  a: 2,\n}".to_owned())
+    );
+}